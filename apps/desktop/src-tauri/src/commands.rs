@@ -1,112 +1,1009 @@
+use crate::config::{Config, ConfigPatch};
+use crate::diagnostics;
+use crate::env;
+use crate::error::AppError;
+use crate::events;
+use crate::keychain;
+use crate::metrics;
+use crate::notification;
+use crate::paths;
+use crate::process;
 use crate::server;
+use crate::shortcuts;
+use crate::snapshot;
+use crate::tray::{self, ServiceState};
 use crate::tunnel;
-use crate::ServerState;
-use crate::TunnelState;
-use tauri::State;
+use crate::window;
+use crate::AppState;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use tauri::{Emitter, Manager, State};
+
+/// Check a server config for problems without starting it. The request is
+/// merged onto the saved settings the same way `start_server` does.
+#[tauri::command]
+pub async fn validate_server_config(
+    state: State<'_, AppState>,
+    config: server::ServerConfigRequest,
+) -> Result<Vec<server::ValidationWarning>, AppError> {
+    let defaults = state.config.lock().await.server_config();
+    Ok(server::validate_config(&config.merge_into(defaults)))
+}
 
 #[tauri::command]
 pub async fn start_server(
-    state: State<'_, ServerState>,
-    port: u16,
-) -> Result<String, String> {
-    let mut server_state = state.0.lock().await;
+    state: State<'_, AppState>,
+    port: Option<u16>,
+    config: Option<server::ServerConfigRequest>,
+) -> Result<String, AppError> {
+    let app = &state.app_handle;
+    let defaults = state.config.lock().await.server_config();
+    let mut server_state = state.server.lock().await;
     if server_state.is_some() {
-        return Err("Server is already running".to_string());
+        return Err(AppError::ServerAlreadyRunning);
     }
 
-    let handle = server::start(port).await.map_err(|e| e.to_string())?;
+    let mut config = config.unwrap_or_default().merge_into(defaults);
+    // A bare `port` argument (the older calling convention) wins
+    if let Some(port) = port {
+        config.port = port;
+    }
+    tray::set_server_state(app, ServiceState::Starting);
+    let handle = match server::start(config, state.server_logs.clone()).await {
+        Ok(handle) => handle,
+        Err(e) => {
+            events::emit_server_changed(app, &ServerStatus::of(None, &state.server_history));
+            return Err(e);
+        }
+    };
+    server::spawn_crash_watcher(app.clone(), &handle);
+    let port = handle.port;
+    state.last_server_port.store(port, Ordering::SeqCst);
     *server_state = Some(handle);
+    events::emit_server_changed(app, &ServerStatus::of(server_state.as_ref(), &state.server_history));
+    server::emit_socket_activation(app);
     Ok(format!("Server started on port {}", port))
 }
 
 #[tauri::command]
-pub async fn stop_server(state: State<'_, ServerState>) -> Result<String, String> {
-    let mut server_state = state.0.lock().await;
+pub async fn stop_server(state: State<'_, AppState>) -> Result<String, AppError> {
+    let app = &state.app_handle;
+    let mut server_state = state.server.lock().await;
     if server_state.is_none() {
-        return Err("Server is not running".to_string());
+        return Err(AppError::ServerNotRunning);
     }
 
     let handle = server_state.take().unwrap();
-    server::stop(handle).await.map_err(|e| e.to_string())?;
+    let result = server::stop(handle).await;
+    // The handle is gone either way
+    events::emit_server_changed(app, &ServerStatus::of(None, &state.server_history));
+    result?;
     Ok("Server stopped".to_string())
 }
 
+/// Spacing of health probes after a restart
+const RESTART_HEALTH_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Stop the running server (if any) and start a new one, holding the server
+/// lock for the whole sequence so no other command sees a half-restarted
+/// state. Uses `port` if given, otherwise the previous server's port.
 #[tauri::command]
-pub async fn get_server_status(state: State<'_, ServerState>) -> Result<ServerStatus, String> {
-    let server_state = state.0.lock().await;
-    let running = server_state.is_some();
-    let port = server_state.as_ref().map(|h| h.port).unwrap_or(8787);
-    Ok(ServerStatus { running, port })
+pub async fn restart_server(
+    state: State<'_, AppState>,
+    port: Option<u16>,
+) -> Result<ServerStatus, AppError> {
+    let mut server_state = state.server.lock().await;
+    restart_locked(&state, &mut server_state, "Restart requested", |config| {
+        if let Some(port) = port {
+            config.port = port;
+        }
+    })
+    .await
 }
 
+/// Set the server's extra environment variables and save them to the
+/// config file. A running server is restarted so they take effect. `merge`
+/// overlays `env` onto the current variables instead of replacing them.
 #[tauri::command]
-pub async fn get_server_logs() -> Result<Vec<String>, String> {
-    // TODO: Implement actual log file reading
-    // Read from server log file and return lines
-    Ok(vec!["Server logging not yet implemented".to_string()])
+pub async fn update_server_env(
+    state: State<'_, AppState>,
+    env: HashMap<String, String>,
+    merge: bool,
+) -> Result<ServerStatus, AppError> {
+    let server_env = {
+        let mut config = state.config.lock().await;
+        if !merge {
+            config.server_env.clear();
+        }
+        config.server_env.extend(env);
+        config.save(&state.app_handle)?;
+        config.server_env.clone()
+    };
+    tracing::info!("Server environment: {}", server::mask_env(&server_env));
+
+    let mut server_state = state.server.lock().await;
+    if server_state.is_none() {
+        return Ok(ServerStatus::of(None, &state.server_history));
+    }
+    restart_locked(&state, &mut server_state, "Environment updated", |config| {
+        if merge {
+            config.env.extend(server_env);
+        } else {
+            config.env = server_env;
+        }
+    })
+    .await
 }
 
+/// Result of changing one server environment variable.
 #[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerEnvChange {
+    /// The server is running with the old value; restart it to apply
+    pub restart_required: bool,
+}
+
+/// Set one of the server's extra environment variables and save it to the
+/// config file. Unlike `update_server_env` the running server is left
+/// alone; the result says whether it needs a restart.
+#[tauri::command]
+pub async fn add_server_env_var(
+    state: State<'_, AppState>,
+    key: String,
+    value: String,
+) -> Result<ServerEnvChange, AppError> {
+    if !env::is_valid_key(&key) {
+        return Err(AppError::InvalidArgument(format!(
+            "Invalid environment variable name '{}': use letters, digits and underscores, not starting with a digit",
+            key
+        )));
+    }
+    let shown = if server::is_sensitive_env(&key) { "****" } else { value.as_str() };
+    tracing::info!("Server environment: set {}={}", key, shown);
+    {
+        let mut config = state.config.lock().await;
+        config.server_env.insert(key, value);
+        config.save(&state.app_handle)?;
+    }
+    Ok(ServerEnvChange { restart_required: state.server.lock().await.is_some() })
+}
+
+/// Remove one of the server's extra environment variables and save the
+/// config. Removing a variable that isn't set does nothing.
+#[tauri::command]
+pub async fn remove_server_env_var(state: State<'_, AppState>, key: String) -> Result<ServerEnvChange, AppError> {
+    let removed = {
+        let mut config = state.config.lock().await;
+        let removed = config.server_env.remove(&key).is_some();
+        if removed {
+            config.save(&state.app_handle)?;
+        }
+        removed
+    };
+    if removed {
+        tracing::info!("Server environment: removed {}", key);
+    }
+    Ok(ServerEnvChange { restart_required: removed && state.server.lock().await.is_some() })
+}
+
+/// The saved value of one of the server's extra environment variables, or
+/// `****` if it looks like a secret.
+#[tauri::command]
+pub async fn get_server_env_var(state: State<'_, AppState>, key: String) -> Result<Option<String>, AppError> {
+    let config = state.config.lock().await;
+    Ok(config.server_env.get(&key).map(|value| {
+        if server::is_sensitive_env(&key) { "****".to_string() } else { value.clone() }
+    }))
+}
+
+/// `restart_server` for callers already holding the server lock. `reason`
+/// goes into the restart history; `adjust` can change the config before the
+/// new server starts.
+async fn restart_locked(
+    state: &AppState,
+    server_state: &mut Option<server::ServerHandle>,
+    reason: &str,
+    adjust: impl FnOnce(&mut server::ServerConfig),
+) -> Result<ServerStatus, AppError> {
+    let app = &state.app_handle;
+    let mut config = state.config.lock().await.server_config().build();
+
+    if let Some(handle) = server_state.take() {
+        config = handle.config().clone();
+        config.port = handle.port;
+        tray::set_server_state(app, ServiceState::Starting);
+        if let Err(e) = server::stop(handle).await {
+            events::emit_server_changed(app, &ServerStatus::of(None, &state.server_history));
+            tracing::error!("Failed to stop server for restart: {}", e);
+            return Err(e);
+        }
+    }
+    adjust(&mut config);
+
+    tray::set_server_state(app, ServiceState::Starting);
+    let handle = match server::start(config, state.server_logs.clone()).await {
+        Ok(handle) => handle,
+        Err(e) => {
+            events::emit_server_changed(app, &ServerStatus::of(None, &state.server_history));
+            tracing::error!("Server was stopped but failed to start again: {}", e);
+            return Err(e);
+        }
+    };
+
+    // Probe for roughly the configured startup timeout
+    let retries = (handle.config().startup_timeout.as_millis() / RESTART_HEALTH_DELAY.as_millis())
+        .clamp(1, u8::MAX as u128) as u8;
+    if let Err(e) = server::probe_server_health(handle.port, &handle.config().health_check, retries, RESTART_HEALTH_DELAY).await {
+        let _ = server::stop(handle).await;
+        events::emit_server_changed(app, &ServerStatus::of(None, &state.server_history));
+        tracing::error!("Server restarted but never became healthy: {}", e);
+        return Err(e);
+    }
+    server::spawn_crash_watcher(app.clone(), &handle);
+
+    state.server_history.record(reason);
+    let status = ServerStatus::of(Some(&handle), &state.server_history);
+    state.last_server_port.store(handle.port, Ordering::SeqCst);
+    *server_state = Some(handle);
+    events::emit_server_changed(app, &status);
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn get_server_status(state: State<'_, AppState>) -> Result<ServerStatus, AppError> {
+    Ok(live_server_status(&state).await)
+}
+
+/// Status of the current server, with `running` checked against the
+/// process rather than taken from the handle's presence.
+async fn live_server_status(state: &AppState) -> ServerStatus {
+    let mut server_state = state.server.lock().await;
+    let alive = match server_state.as_mut() {
+        Some(handle) => server::is_alive(handle).await,
+        None => false,
+    };
+    let status = ServerStatus::of(server_state.as_ref(), &state.server_history);
+    ServerStatus { running: alive, ..status }
+}
+
+/// Send `message` as a line on the server's stdin, for servers that take
+/// commands there. The server must have been started with `captureStdin`.
+#[tauri::command]
+pub async fn send_server_stdin(state: State<'_, AppState>, message: String) -> Result<(), AppError> {
+    let server_state = state.server.lock().await;
+    let handle = server_state.as_ref().ok_or(AppError::ServerNotRunning)?;
+    handle.write_stdin(&message).await
+}
+
+/// Server restarts this app session, oldest first.
+#[tauri::command]
+pub async fn get_server_history(state: State<'_, AppState>) -> Result<Vec<server::RestartEvent>, AppError> {
+    Ok(state.server_history.events())
+}
+
+/// Close the crash-restart circuit breaker so crashes are restarted again.
+/// Does not start a stopped server.
+#[tauri::command]
+pub async fn reset_circuit_breaker(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.server_circuit.reset();
+    tracing::info!("Circuit breaker reset");
+    Ok(())
+}
+
+/// OS process ID of the running server, for attaching debuggers and the
+/// like. `None` once the process has exited, even before a restart.
+#[tauri::command]
+pub async fn get_server_pid(state: State<'_, AppState>) -> Result<Option<u32>, AppError> {
+    Ok(state.server.lock().await.as_ref().and_then(|h| h.pid()))
+}
+
+/// Seconds since the current server process started, or `None` if it is
+/// not running. Restarts (including crash recovery) start from zero.
+#[tauri::command]
+pub async fn get_server_uptime(state: State<'_, AppState>) -> Result<Option<u64>, AppError> {
+    let server_state = state.server.lock().await;
+    Ok(server_state.as_ref().map(|h| h.uptime().as_secs()))
+}
+
+/// Captured server output, oldest first. `since` is a Unix time in
+/// milliseconds; `level_filter` and `search` narrow the lines further.
+#[tauri::command]
+pub async fn get_server_logs(
+    state: State<'_, AppState>,
+    since: Option<u64>,
+    limit: Option<usize>,
+    level_filter: Option<String>,
+    search: Option<String>,
+) -> Result<Vec<server::LogLine>, AppError> {
+    Ok(state.server_logs.query(&server::LogQuery {
+        since_ms: since,
+        level: level_filter,
+        search,
+        limit,
+    }))
+}
+
+/// Start emitting a `server-log` event for every new line of server output.
+#[tauri::command]
+pub async fn subscribe_server_logs(state: State<'_, AppState>) -> Result<(), AppError> {
+    let app = state.app_handle.clone();
+    let server_state = state.server.lock().await;
+    let handle = server_state.as_ref().ok_or(AppError::ServerNotRunning)?;
+
+    // Already forwarding for this server instance
+    let Some(mut receiver) = handle.subscribe_logs() else {
+        return Ok(());
+    };
+
+    tauri::async_runtime::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+
+        loop {
+            match receiver.recv().await {
+                Ok(line) => {
+                    let _ = app.emit("server-log", line);
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Write the captured server output to `path`. Returns the number of lines
+/// written.
+#[tauri::command]
+pub async fn export_logs(
+    state: State<'_, AppState>,
+    path: PathBuf,
+    format: Option<server::ExportFormat>,
+) -> Result<u64, AppError> {
+    let count = state.server_logs.export(&path, format.unwrap_or_default())?;
+    tracing::info!("Exported {} log lines to {}", count, path.display());
+    Ok(count)
+}
+
+/// Empty the buffered server output and the server log file, then emit
+/// `server-logs-cleared` so open log views can refresh.
+#[tauri::command]
+pub async fn clear_server_logs(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.server_logs.clear().await?;
+    let _ = state.app_handle.emit(
+        "server-logs-cleared",
+        serde_json::json!({ "timestamp_ms": chrono::Utc::now().timestamp_millis() }),
+    );
+    Ok(())
+}
+
+/// Where the app keeps its config, logs and window state, for display.
+#[tauri::command]
+pub async fn get_app_data_dir(app: tauri::AppHandle) -> Result<String, AppError> {
+    let dir = app.path().app_data_dir().map_err(|e| {
+        format!(
+            "Failed to resolve app data dir: {}. In a sandboxed build, check that the appDir permission is enabled",
+            e
+        )
+    })?;
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+/// Path of the server log currently being written to.
+#[tauri::command]
+pub async fn get_log_file_path(state: State<'_, AppState>) -> Result<String, AppError> {
+    let file = state
+        .server_logs
+        .file()
+        .ok_or("Server output isn't being written to a log file")?;
+    Ok(file.lock().await.path().to_string_lossy().into_owned())
+}
+
+/// Open the app data directory (config, logs, window state, screenshots) in the
+/// system file manager.
+#[tauri::command]
+pub async fn open_log_directory(app: tauri::AppHandle) -> Result<(), AppError> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    window::open_in_file_manager(&app, &dir)
+}
+
+/// Open the directory the server runs from in the system file manager.
+#[tauri::command]
+pub async fn open_server_directory(app: tauri::AppHandle) -> Result<(), AppError> {
+    let dir = paths::find_server_directory()?;
+    window::open_in_file_manager(&app, &dir)
+}
+
+/// Show `path` (an exported log, say) selected in its folder where the file
+/// manager supports it, or just open the folder where it doesn't.
+#[tauri::command]
+pub async fn reveal_file_in_manager(path: PathBuf) -> Result<(), AppError> {
+    window::reveal_in_file_manager(&path).await
+}
+
+/// Install the server's npm dependencies, streaming the installer output
+/// as `install-log` events.
+#[tauri::command]
+pub async fn install_server_dependencies(state: State<'_, AppState>) -> Result<String, AppError> {
+    let server_dir = paths::find_server_directory()?;
+    server::install_dependencies(&state.app_handle, &server_dir).await?;
+    Ok("Dependencies installed successfully".to_string())
+}
+
+/// Run an npm script (a migration, seed or code generator) in the server
+/// directory and return its output. Only scripts listed in
+/// `Config::allowed_scripts` may run.
+#[tauri::command]
+pub async fn exec_in_server_dir(
+    state: State<'_, AppState>,
+    script_name: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+) -> Result<server::ExecResult, AppError> {
+    let (allowed, timeout) = {
+        let config = state.config.lock().await;
+        (
+            config.allowed_scripts.contains(&script_name),
+            std::time::Duration::from_secs(config.script_timeout_secs),
+        )
+    };
+    if !allowed {
+        return Err(format!("Script '{}' is not in allowed_scripts in config.toml", script_name).into());
+    }
+
+    let server_dir = paths::find_server_directory()?;
+    server::run_server_script(&server_dir, &script_name, &args, &env, timeout).await
+}
+
+#[tauri::command]
+pub async fn check_dependencies() -> Result<server::DependencyStatus, AppError> {
+    let server_dir = paths::find_server_directory()?;
+    Ok(server::dependency_status(&server_dir))
+}
+
+#[tauri::command]
+pub async fn get_engine_requirements() -> Result<Option<server::NodeEngine>, AppError> {
+    Ok(server::engine_requirements())
+}
+
+#[derive(Clone, Default, serde::Serialize)]
 pub struct ServerStatus {
     pub running: bool,
     pub port: u16,
+    pub uptime_secs: Option<u64>,
+    pub pid: Option<u32>,
+    /// Restarts this app session, requested or after a crash
+    pub restart_count: u32,
+    pub last_restart_reason: Option<String>,
+    /// Exit code of a server that has stopped on its own; `None` while it
+    /// runs or when it was killed by a signal
+    pub exit_code: Option<i32>,
+    /// Health checks failed in a row so far
+    pub watchdog_failures: u32,
+    /// Seconds since the last passing health check, if there was one
+    pub last_watchdog_success_secs: Option<u64>,
+}
+
+impl ServerStatus {
+    /// Status of the server behind `handle`, or of no server at all.
+    pub fn of(handle: Option<&server::ServerHandle>, history: &server::RestartHistory) -> Self {
+        ServerStatus {
+            running: handle.is_some(),
+            port: handle.map(|h| h.port).unwrap_or(window::DEFAULT_SERVER_PORT),
+            uptime_secs: handle.map(|h| h.uptime().as_secs()),
+            pid: handle.and_then(|h| h.pid()),
+            restart_count: history.count(),
+            last_restart_reason: history.last_reason(),
+            exit_code: handle.and_then(|h| h.exit_status).and_then(|s| s.code()),
+            watchdog_failures: handle.map_or(0, |h| h.watchdog_failures()),
+            last_watchdog_success_secs: handle
+                .and_then(|h| h.last_watchdog_success())
+                .map(|at| at.elapsed().as_secs()),
+        }
+    }
+
+    /// The same status, for a server that has just exited with `status`.
+    pub fn exited_with(self, status: std::process::ExitStatus) -> Self {
+        ServerStatus { running: false, exit_code: status.code(), ..self }
+    }
+}
+
+const MAX_BENCHMARK_CONCURRENCY: u32 = 100;
+const MAX_BENCHMARK_REQUESTS: u32 = 10_000;
+
+#[tauri::command]
+pub async fn benchmark_server(
+    state: State<'_, AppState>,
+    path: String,
+    method: String,
+    body: Option<String>,
+    concurrency: u32,
+    requests: u32,
+) -> Result<BenchmarkResult, AppError> {
+    if !(1..=MAX_BENCHMARK_CONCURRENCY).contains(&concurrency) {
+        return Err(AppError::InvalidArgument(format!(
+            "Concurrency must be between 1 and {}",
+            MAX_BENCHMARK_CONCURRENCY
+        )));
+    }
+    if !(1..=MAX_BENCHMARK_REQUESTS).contains(&requests) {
+        return Err(AppError::InvalidArgument(format!(
+            "Request count must be between 1 and {}",
+            MAX_BENCHMARK_REQUESTS
+        )));
+    }
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid HTTP method: {}", method))?;
+
+    let port = {
+        let server_state = state.server.lock().await;
+        server_state.as_ref().ok_or(AppError::ServerNotRunning)?.port
+    };
+    let path = if path.starts_with('/') { path } else { format!("/{}", path) };
+    let url = format!("http://localhost:{}{}", port, path);
+
+    // Run on the async runtime's worker pool rather than the command task
+    tauri::async_runtime::spawn(run_benchmark(url, method, body, concurrency, requests))
+        .await
+        .map_err(|e| format!("Benchmark task failed: {}", e))?
+}
+
+#[derive(serde::Serialize)]
+pub struct BenchmarkResult {
+    pub requests: u32,
+    pub errors: u32,
+    pub rps: f64,
+    pub latency_min_ms: f64,
+    pub latency_max_ms: f64,
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    pub latency_p99_ms: f64,
+    pub elapsed_ms: u64,
+}
+
+async fn run_benchmark(
+    url: String,
+    method: reqwest::Method,
+    body: Option<String>,
+    concurrency: u32,
+    requests: u32,
+) -> Result<BenchmarkResult, AppError> {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    // Workers pull from a shared counter so the total is exact regardless
+    // of how requests divide across connections
+    let remaining = Arc::new(AtomicU32::new(requests));
+    let started = std::time::Instant::now();
+
+    let mut workers = Vec::with_capacity(concurrency as usize);
+    for _ in 0..concurrency {
+        let client = client.clone();
+        let url = url.clone();
+        let method = method.clone();
+        let body = body.clone();
+        let remaining = Arc::clone(&remaining);
+
+        workers.push(tokio::spawn(async move {
+            let mut latencies = Vec::new();
+            let mut errors = 0u32;
+            while remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                let mut request = client.request(method.clone(), &url);
+                if let Some(body) = &body {
+                    request = request.body(body.clone());
+                }
+
+                let sent = std::time::Instant::now();
+                match request.send().await {
+                    Ok(response) if response.status().is_success() => {
+                        latencies.push(sent.elapsed().as_secs_f64() * 1000.0);
+                    }
+                    _ => errors += 1,
+                }
+            }
+            (latencies, errors)
+        }));
+    }
+
+    let mut latencies = Vec::with_capacity(requests as usize);
+    let mut errors = 0u32;
+    for worker in workers {
+        let (worker_latencies, worker_errors) = worker
+            .await
+            .map_err(|e| format!("Benchmark worker failed: {}", e))?;
+        latencies.extend(worker_latencies);
+        errors += worker_errors;
+    }
+
+    let elapsed = started.elapsed();
+    latencies.sort_by(|a, b| a.total_cmp(b));
+
+    Ok(BenchmarkResult {
+        requests,
+        errors,
+        rps: f64::from(requests) / elapsed.as_secs_f64().max(f64::EPSILON),
+        latency_min_ms: latencies.first().copied().unwrap_or(0.0),
+        latency_max_ms: latencies.last().copied().unwrap_or(0.0),
+        latency_p50_ms: percentile(&latencies, 50.0),
+        latency_p95_ms: percentile(&latencies, 95.0),
+        latency_p99_ms: percentile(&latencies, 99.0),
+        elapsed_ms: elapsed.as_millis() as u64,
+    })
+}
+
+/// Nearest-rank percentile over an already sorted slice.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
 }
 
 // Tunnel commands
+/// Start a tunnel to `port`. Anything not given as an argument or in
+/// `config` comes from the configured preferences.
 #[tauri::command]
 pub async fn start_tunnel(
-    state: State<'_, TunnelState>,
+    state: State<'_, AppState>,
     port: u16,
-) -> Result<String, String> {
-    let mut tunnel_state = state.0.lock().await;
+    provider: Option<tunnel::TunnelProvider>,
+    subdomain: Option<String>,
+    config: Option<tunnel::TunnelConfigRequest>,
+) -> Result<String, AppError> {
+    let app = &state.app_handle;
+    let running_check = state.server.lock().await.as_ref().map(|h| h.config().health_check.clone());
+    let defaults = {
+        let config = state.config.lock().await;
+        tunnel::TunnelConfig {
+            port,
+            provider: config.preferred_tunnel_provider.clone(),
+            subdomain: config.tunnel_subdomain.clone(),
+            server_health_check: running_check.unwrap_or_else(|| config.server_config().build().health_check),
+            ..Default::default()
+        }
+    };
+    let mut config = config.unwrap_or_default().merge_into(defaults);
+    // The bare arguments (the older calling convention) win
+    config.port = port;
+    if let Some(provider) = provider {
+        config.provider = provider;
+    }
+    if let Some(subdomain) = subdomain {
+        config.subdomain = Some(subdomain);
+    }
+
+    let mut tunnel_state = state.tunnel.lock().await;
     if tunnel_state.is_some() {
-        return Err("Tunnel is already running".to_string());
+        return Err(AppError::TunnelAlreadyRunning);
     }
 
-    let handle = tunnel::start(port).await.map_err(|e| e.to_string())?;
+    if config.preflight_check {
+        let network = tunnel::network_check(config.port).await;
+        if !network.internet_available {
+            return Err(AppError::NoInternetConnection);
+        }
+        // With wait_for_server, tunnel::start waits for it instead
+        if !network.local_server_reachable && !config.wait_for_server {
+            tracing::warn!("Nothing is listening on port {} yet; the tunnel may show errors", config.port);
+        }
+    }
+
+    tray::set_tunnel_state(app, ServiceState::Starting);
+    let handle = match tunnel::start(config).await {
+        Ok(handle) => handle,
+        Err(e) => {
+            events::emit_tunnel_changed(app, &TunnelStatus::of(None, &state.tunnel_history).await);
+            return Err(e);
+        }
+    };
     let url = tunnel::get_url(&handle).await;
+    if let Some(url) = &url {
+        tunnel::check_subdomain(app, &handle, url);
+        notification::show(app, "Tunnel ready", url.clone());
+    }
 
+    tunnel::spawn_reconnect_watcher(app.clone(), &handle);
     *tunnel_state = Some(handle);
+    events::emit_tunnel_changed(app, &TunnelStatus::of(tunnel_state.as_ref(), &state.tunnel_history).await);
 
     match url {
         Some(u) => Ok(u),
-        None => Err("Tunnel started but URL not available".to_string()),
+        None => Err("Tunnel started but URL not available".into()),
     }
 }
 
+/// Save a tunnel auth token in the OS keychain under `provider`
+/// (`cloudflared`, or a custom command's name). Tunnels started without
+/// an explicit token use it.
+#[tauri::command]
+pub async fn secure_store_tunnel_token(provider: String, token: String) -> Result<(), AppError> {
+    keychain::set_secret(&provider, &token).await?;
+    tracing::info!("Saved the {} tunnel token to the keychain", provider);
+    Ok(())
+}
+
+/// The token saved with `secure_store_tunnel_token`, if any.
 #[tauri::command]
-pub async fn stop_tunnel(state: State<'_, TunnelState>) -> Result<String, String> {
-    let mut tunnel_state = state.0.lock().await;
+pub async fn get_tunnel_token(provider: String) -> Result<Option<String>, AppError> {
+    keychain::get_secret(&provider).await
+}
+
+/// Tunnel providers whose binaries were found on PATH.
+#[tauri::command]
+pub async fn get_tunnel_providers() -> Result<Vec<tunnel::TunnelProvider>, AppError> {
+    Ok(tunnel::detect_available_providers())
+}
+
+/// Check internet access and whether the server's port answers, e.g.
+/// before offering to start a tunnel.
+#[tauri::command]
+pub async fn network_check(state: State<'_, AppState>) -> Result<tunnel::NetworkStatus, AppError> {
+    let running = state.server.lock().await.as_ref().map(|h| h.port);
+    let port = match running {
+        Some(port) => port,
+        None => state.config.lock().await.server_port,
+    };
+    Ok(tunnel::network_check(port).await)
+}
+
+#[tauri::command]
+pub async fn stop_tunnel(state: State<'_, AppState>) -> Result<String, AppError> {
+    let app = &state.app_handle;
+    let mut tunnel_state = state.tunnel.lock().await;
     if tunnel_state.is_none() {
-        return Err("Tunnel is not running".to_string());
+        return Err(AppError::TunnelNotRunning);
     }
 
     let handle = tunnel_state.take().unwrap();
-    tunnel::stop(handle).await.map_err(|e| e.to_string())?;
+    // They point at a URL that is about to stop working
+    state.share_links.lock().await.clear();
+    let result = tunnel::stop(handle).await;
+    events::emit_tunnel_changed(app, &TunnelStatus::of(None, &state.tunnel_history).await);
+    result?;
     Ok("Tunnel stopped".to_string())
 }
 
+/// Outcome of `stop_all`. One of the two failing doesn't hide that the
+/// other stopped.
+#[derive(serde::Serialize)]
+pub struct StopAllResult {
+    pub server_stopped: bool,
+    pub tunnel_stopped: bool,
+    pub server_error: Option<String>,
+    pub tunnel_error: Option<String>,
+}
+
+/// Stop the server and the tunnel at the same time, for app quit. Whatever
+/// isn't running is skipped rather than reported as an error.
 #[tauri::command]
-pub async fn get_tunnel_status(state: State<'_, TunnelState>) -> Result<TunnelStatus, String> {
-    let tunnel_state = state.0.lock().await;
-    let running = tunnel_state.is_some();
-    let url = if let Some(handle) = tunnel_state.as_ref() {
-        tunnel::get_url(handle).await
-    } else {
-        None
+pub async fn stop_all(state: State<'_, AppState>) -> Result<StopAllResult, AppError> {
+    let app = &state.app_handle;
+    let (server_handle, tunnel_handle) = {
+        let mut server_state = state.server.lock().await;
+        let mut tunnel_state = state.tunnel.lock().await;
+        (server_state.take(), tunnel_state.take())
     };
-    Ok(TunnelStatus { running, url })
+    let (server_stopped, tunnel_stopped) = (server_handle.is_some(), tunnel_handle.is_some());
+
+    let (server_result, tunnel_result) = tokio::join!(
+        async {
+            match server_handle {
+                Some(handle) => server::stop(handle).await,
+                None => Ok(()),
+            }
+        },
+        async {
+            match tunnel_handle {
+                Some(handle) => tunnel::stop(handle).await,
+                None => Ok(()),
+            }
+        },
+    );
+
+    // Both handles are gone either way
+    if server_stopped {
+        events::emit_server_changed(app, &ServerStatus::of(None, &state.server_history));
+    }
+    if tunnel_stopped {
+        state.share_links.lock().await.clear();
+        events::emit_tunnel_changed(app, &TunnelStatus::of(None, &state.tunnel_history).await);
+    }
+
+    Ok(StopAllResult {
+        server_stopped: server_stopped && server_result.is_ok(),
+        tunnel_stopped: tunnel_stopped && tunnel_result.is_ok(),
+        server_error: server_result.err().map(|e| e.to_string()),
+        tunnel_error: tunnel_result.err().map(|e| e.to_string()),
+    })
 }
 
 #[derive(serde::Serialize)]
+pub struct TunnelStats {
+    /// Requests the provider reported; 0 for providers that don't log them
+    pub request_count: u64,
+    /// No provider reports traffic volume yet, so this is always 0
+    pub bytes_proxied: u64,
+    pub uptime_secs: u64,
+    pub url: Option<String>,
+}
+
+/// Traffic through the running tunnel, as far as its provider reports it.
+#[tauri::command]
+pub async fn get_tunnel_stats(state: State<'_, AppState>) -> Result<TunnelStats, AppError> {
+    let tunnel_state = state.tunnel.lock().await;
+    let handle = tunnel_state.as_ref().ok_or(AppError::TunnelNotRunning)?;
+    Ok(TunnelStats {
+        request_count: handle.request_count(),
+        bytes_proxied: 0,
+        uptime_secs: handle.uptime().as_secs(),
+        url: tunnel::get_url(handle).await,
+    })
+}
+
+#[tauri::command]
+pub async fn get_tunnel_status(state: State<'_, AppState>) -> Result<TunnelStatus, AppError> {
+    Ok(TunnelStatus::of(state.tunnel.lock().await.as_ref(), &state.tunnel_history).await)
+}
+
+/// Server and tunnel status in one call, for the initial status fetch.
+/// Each lock is released before the next is taken.
+#[tauri::command]
+pub async fn get_combined_status(state: State<'_, AppState>) -> Result<CombinedStatus, AppError> {
+    let server = live_server_status(&state).await;
+    let tunnel = TunnelStatus::of(state.tunnel.lock().await.as_ref(), &state.tunnel_history).await;
+    Ok(CombinedStatus { server, tunnel })
+}
+
+#[derive(serde::Serialize)]
+pub struct CombinedStatus {
+    pub server: ServerStatus,
+    pub tunnel: TunnelStatus,
+}
+
+#[derive(Clone, Default, serde::Serialize)]
 pub struct TunnelStatus {
     pub running: bool,
     pub url: Option<String>,
+    /// The subdomain actually assigned, which may differ from the one asked for
+    pub subdomain: Option<String>,
+    pub uptime_secs: Option<u64>,
+    /// Reconnects this app session
+    pub restart_count: u32,
+    pub last_restart_reason: Option<String>,
+}
+
+impl TunnelStatus {
+    /// Status of the tunnel behind `handle`, or of no tunnel at all.
+    pub async fn of(handle: Option<&tunnel::TunnelHandle>, history: &server::RestartHistory) -> Self {
+        let url = match handle {
+            Some(handle) => tunnel::get_url(handle).await,
+            None => None,
+        };
+        let subdomain = url.as_deref().and_then(tunnel::subdomain_of);
+        TunnelStatus {
+            running: handle.is_some(),
+            url,
+            subdomain,
+            uptime_secs: handle.map(|h| h.uptime().as_secs()),
+            restart_count: history.count(),
+            last_restart_reason: history.last_reason(),
+        }
+    }
+}
+
+// Share link commands
+//
+// Links are kept in memory only and disappear when the app quits or the
+// tunnel stops. They are local bookkeeping: a token is only checked by
+// `resolve_share_link` inside this app, and nothing in front of the tunnel
+// asks for one, so they don't restrict who can reach the tunnel URL.
+
+/// Record the running tunnel's URL under a new token that expires after
+/// `expiry_minutes`, for tracking (and counting uses of) a URL passed on.
+#[tauri::command]
+pub async fn create_tunnel_share_link(
+    state: State<'_, AppState>,
+    expiry_minutes: u32,
+    label: Option<String>,
+) -> Result<ShareLink, AppError> {
+    if expiry_minutes == 0 {
+        return Err(AppError::InvalidArgument("Expiry must be at least one minute".to_string()));
+    }
+
+    let tunnel_url = {
+        let tunnel_state = state.tunnel.lock().await;
+        let handle = tunnel_state.as_ref().ok_or(AppError::TunnelNotRunning)?;
+        tunnel::get_url(handle)
+            .await
+            .ok_or("Tunnel URL is not available yet")?
+    };
+
+    let token = uuid::Uuid::new_v4().to_string();
+    let link = ShareLink {
+        token: token.clone(),
+        tunnel_url,
+        expires_at: unix_now() + u64::from(expiry_minutes) * 60,
+        label,
+        uses: 0,
+    };
+
+    state.share_links.lock().await.insert(token, link.clone());
+    Ok(link)
+}
+
+#[tauri::command]
+pub async fn revoke_share_link(
+    state: State<'_, AppState>,
+    token: String,
+) -> Result<(), AppError> {
+    match state.share_links.lock().await.remove(&token) {
+        Some(_) => Ok(()),
+        None => Err(format!("Share link not found: {}", token).into()),
+    }
+}
+
+/// The tunnel behind `token`, counting the use. Expired links are removed
+/// and refused.
+#[tauri::command]
+pub async fn resolve_share_link(
+    state: State<'_, AppState>,
+    token: String,
+) -> Result<ShareLink, AppError> {
+    let mut links = state.share_links.lock().await;
+    let link = links
+        .get_mut(&token)
+        .ok_or_else(|| format!("Share link not found: {}", token))?;
+    if link.expires_at <= unix_now() {
+        links.remove(&token);
+        return Err(format!("Share link has expired: {}", token).into());
+    }
+    link.uses += 1;
+    Ok(link.clone())
+}
+
+#[tauri::command]
+pub async fn list_share_links(state: State<'_, AppState>) -> Result<Vec<ShareLink>, AppError> {
+    let mut links = state.share_links.lock().await;
+    let now = unix_now();
+    links.retain(|_, link| link.expires_at > now);
+
+    let mut list: Vec<ShareLink> = links.values().cloned().collect();
+    list.sort_by_key(|link| link.expires_at);
+    Ok(list)
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ShareLink {
+    pub token: String,
+    pub tunnel_url: String,
+    /// Unix timestamp (seconds) after which the link is no longer valid
+    pub expires_at: u64,
+    pub label: Option<String>,
+    pub uses: u32,
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
 // Environment check commands
 #[tauri::command]
-pub async fn check_environment() -> Result<EnvironmentInfo, String> {
+pub async fn check_environment() -> Result<EnvironmentInfo, AppError> {
     let node_info = check_command_version("node", &["--version"]).await;
     let npm_info = check_command_version("npm", &["--version"]).await;
     let pnpm_info = check_command_version("pnpm", &["--version"]).await;
@@ -118,8 +1015,29 @@ pub async fn check_environment() -> Result<EnvironmentInfo, String> {
     })
 }
 
+/// Whether a usable Node.js binary can be found, honouring the configured
+/// `node_binary_path`.
+#[tauri::command]
+pub async fn check_node_installed(state: State<'_, AppState>) -> Result<bool, AppError> {
+    let configured = state.config.lock().await.node_binary_path.clone();
+    Ok(server::resolve_node_binary(configured.as_deref()).is_ok())
+}
+
 #[tauri::command]
-pub async fn check_port(port: u16) -> Result<PortStatus, String> {
+pub async fn get_node_version(state: State<'_, AppState>) -> Result<String, AppError> {
+    Ok(get_node_info(state).await?.version)
+}
+
+#[tauri::command]
+pub async fn get_node_info(state: State<'_, AppState>) -> Result<NodeInfo, AppError> {
+    let configured = state.config.lock().await.node_binary_path.clone();
+    let path = server::resolve_node_binary(configured.as_deref())?;
+    let version = server::program_version(&path).await?;
+    Ok(NodeInfo { path, version })
+}
+
+#[tauri::command]
+pub async fn check_port(port: u16) -> Result<PortStatus, AppError> {
     use std::net::TcpListener;
 
     let available = TcpListener::bind(format!("127.0.0.1:{}", port)).is_ok();
@@ -131,6 +1049,321 @@ pub async fn check_port(port: u16) -> Result<PortStatus, String> {
     })
 }
 
+/// Free ports from the default server port upwards, for the frontend to
+/// suggest when the chosen one is taken.
+#[tauri::command]
+pub async fn get_available_ports(count: u8) -> Result<Vec<u16>, AppError> {
+    let ports = server::available_ports(window::DEFAULT_SERVER_PORT, count as usize);
+    if ports.is_empty() && count > 0 {
+        return Err("No free ports available".into());
+    }
+    Ok(ports)
+}
+
+/// Total app memory above which `app://high-memory-usage` is emitted
+const MEMORY_WARNING_THRESHOLD_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+#[tauri::command]
+pub async fn get_app_memory_usage(state: State<'_, AppState>) -> Result<AppMemoryUsage, AppError> {
+    use sysinfo::{Pid, System};
+
+    let app = &state.app_handle;
+
+    let server_pid = state.server.lock().await.as_ref().and_then(|h| h.pid());
+
+    let mut system = System::new();
+    system.refresh_processes();
+    let processes = system.processes();
+
+    let tauri_pid = sysinfo::get_current_pid()
+        .map_err(|e| format!("Failed to get current process: {}", e))?;
+    let tauri_process_bytes = processes
+        .get(&tauri_pid)
+        .map(|p| p.memory())
+        .unwrap_or(0);
+
+    // Both the server and the webview host run as descendants of this
+    // process; count the server's subtree separately from the rest
+    let descendants_of = |root: Pid| -> Vec<Pid> {
+        let mut found = vec![root];
+        let mut i = 0;
+        while i < found.len() {
+            let parent = found[i];
+            found.extend(
+                processes
+                    .iter()
+                    .filter(|(_, p)| p.parent() == Some(parent))
+                    .map(|(pid, _)| *pid),
+            );
+            i += 1;
+        }
+        found.remove(0);
+        found
+    };
+    let memory_of = |pids: &[Pid]| -> u64 {
+        pids.iter()
+            .filter_map(|pid| processes.get(pid))
+            .map(|p| p.memory())
+            .sum()
+    };
+
+    let server_tree: Vec<Pid> = server_pid
+        .map(|pid| {
+            let root = Pid::from_u32(pid);
+            let mut tree = descendants_of(root);
+            tree.push(root);
+            tree
+        })
+        .unwrap_or_default();
+    let server_process_bytes = server_pid.map(|_| memory_of(&server_tree));
+
+    let webview_pids: Vec<Pid> = descendants_of(tauri_pid)
+        .into_iter()
+        .filter(|pid| !server_tree.contains(pid))
+        .collect();
+    let webview_process_bytes = memory_of(&webview_pids);
+
+    let total_bytes =
+        tauri_process_bytes + server_process_bytes.unwrap_or(0) + webview_process_bytes;
+    if total_bytes > MEMORY_WARNING_THRESHOLD_BYTES {
+        let _ = app.emit(
+            "app://high-memory-usage",
+            serde_json::json!({ "bytes": total_bytes }),
+        );
+    }
+
+    Ok(AppMemoryUsage {
+        tauri_process_bytes,
+        server_process_bytes,
+        webview_process_bytes,
+        total_bytes,
+    })
+}
+
+/// OS, hardware and app version, for attaching to support requests.
+#[tauri::command]
+pub async fn get_system_info(app: tauri::AppHandle) -> Result<SystemInfo, AppError> {
+    use sysinfo::System;
+
+    let mut system = System::new();
+    system.refresh_memory();
+
+    Ok(SystemInfo {
+        os_name: std::env::consts::OS.to_string(),
+        os_version: System::os_version().unwrap_or_else(|| "unknown".to_string()),
+        arch: std::env::consts::ARCH.to_string(),
+        total_memory_mb: system.total_memory() / (1024 * 1024),
+        available_memory_mb: system.available_memory() / (1024 * 1024),
+        cpu_count: std::thread::available_parallelism()
+            .map(|n| n.get() as u32)
+            .unwrap_or(1),
+        app_version: app.package_info().version.to_string(),
+        workspace_tool: paths::find_project_root()
+            .ok()
+            .map(|root| server::detect_workspace_tool(&root)),
+    })
+}
+
+/// A Node.js-related process, from `list_running_node_processes`.
+#[derive(serde::Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub command_line: String,
+    pub memory_bytes: u64,
+}
+
+/// Every process whose name contains `node` or `npx`, for spotting a
+/// server left over from an earlier session that still holds the port.
+#[tauri::command]
+pub async fn list_running_node_processes() -> Result<Vec<ProcessInfo>, AppError> {
+    use sysinfo::System;
+
+    let mut system = System::new();
+    system.refresh_processes();
+    let mut processes: Vec<ProcessInfo> = system
+        .processes()
+        .iter()
+        .filter(|(_, p)| {
+            let name = p.name().to_lowercase();
+            name.contains("node") || name.contains("npx")
+        })
+        .map(|(pid, p)| ProcessInfo {
+            pid: pid.as_u32(),
+            name: p.name().to_string(),
+            command_line: p.cmd().join(" "),
+            memory_bytes: p.memory(),
+        })
+        .collect();
+    processes.sort_by_key(|p| p.pid);
+    Ok(processes)
+}
+
+/// Ask process `pid` to exit, e.g. one found by
+/// `list_running_node_processes`. Refuses this app and its parent.
+#[tauri::command]
+pub async fn kill_process(pid: u32) -> Result<(), AppError> {
+    use sysinfo::{Pid, System};
+
+    let current = sysinfo::get_current_pid()
+        .map_err(|e| format!("Failed to get current process: {}", e))?;
+    let mut system = System::new();
+    system.refresh_process(current);
+    let parent = system.process(current).and_then(|p| p.parent());
+    if Pid::from_u32(pid) == current || Some(Pid::from_u32(pid)) == parent {
+        return Err(format!("Refusing to kill process {}: it is this app or its parent", pid).into());
+    }
+
+    process::terminate_pid(pid)?;
+    tracing::info!("Sent terminate to process {}", pid);
+    Ok(())
+}
+
+/// Longest `disable_watchdog_temporarily` accepts
+const MAX_WATCHDOG_PAUSE_SECS: u64 = 24 * 60 * 60;
+
+/// Skip the running server's health checks for `duration_secs`, so a server
+/// that will be briefly unresponsive isn't taken for frozen and restarted.
+#[tauri::command]
+pub async fn disable_watchdog_temporarily(state: State<'_, AppState>, duration_secs: u64) -> Result<(), AppError> {
+    if !(1..=MAX_WATCHDOG_PAUSE_SECS).contains(&duration_secs) {
+        return Err(AppError::InvalidArgument(format!(
+            "Duration must be between 1 and {} seconds",
+            MAX_WATCHDOG_PAUSE_SECS
+        )));
+    }
+    let server_state = state.server.lock().await;
+    let handle = server_state.as_ref().ok_or(AppError::ServerNotRunning)?;
+    handle.pause_watchdog(std::time::Duration::from_secs(duration_secs));
+    tracing::info!("Health checks paused for {}s", duration_secs);
+    Ok(())
+}
+
+/// The health check the running server uses, or the saved one the next
+/// start will use.
+#[tauri::command]
+pub async fn get_health_check_config(state: State<'_, AppState>) -> Result<server::HealthCheck, AppError> {
+    if let Some(handle) = state.server.lock().await.as_ref() {
+        return Ok(handle.config().health_check.clone());
+    }
+    Ok(state.config.lock().await.server_config().build().health_check)
+}
+
+/// Request and error counts, uptime and memory as reported by the
+/// server's metrics endpoint (JSON or Prometheus text). Zeros if it has
+/// none.
+#[tauri::command]
+pub async fn get_server_metrics(state: State<'_, AppState>) -> Result<metrics::ServerMetrics, AppError> {
+    let (port, path) = {
+        let server_state = state.server.lock().await;
+        let handle = server_state.as_ref().ok_or(AppError::ServerNotRunning)?;
+        let path = handle.config().metrics_path.clone();
+        (handle.port, path.unwrap_or_else(|| metrics::DEFAULT_METRICS_PATH.to_string()))
+    };
+    Ok(metrics::fetch(port, &path).await)
+}
+
+/// Requests per minute over the last hour, counted from the buffered server
+/// log lines that match `access_log_pattern`. Works after the server has
+/// stopped, for as long as its lines are still buffered.
+#[tauri::command]
+pub async fn get_requests_per_minute(state: State<'_, AppState>) -> Result<metrics::RpmMetrics, AppError> {
+    let pattern = state.config.lock().await.access_log_pattern.clone();
+    let pattern = pattern.as_deref().unwrap_or(metrics::DEFAULT_ACCESS_LOG_PATTERN);
+    let pattern = regex::Regex::new(pattern)
+        .map_err(|e| AppError::ConfigParseError(format!("Invalid access_log_pattern: {}", e)))?;
+    let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+    let lines = state.server_logs.query(&server::LogQuery {
+        since_ms: Some(now_ms.saturating_sub(60 * 60 * 1000)),
+        ..Default::default()
+    });
+    Ok(metrics::requests_per_minute(&lines, &pattern, now_ms))
+}
+
+/// A process and its descendants, from `get_server_process_tree`.
+#[derive(serde::Serialize)]
+pub struct ProcessNode {
+    pub pid: u32,
+    pub name: String,
+    pub memory_mb: f64,
+    pub children: Vec<ProcessNode>,
+}
+
+/// Guards against parent links that loop through a reused PID
+const MAX_PROCESS_TREE_DEPTH: usize = 32;
+
+/// The server process and everything it spawned. When the server isn't
+/// running, the processes still listening on its last port and their
+/// descendants instead; those are what keeps the port busy after
+/// `stop_server`.
+#[tauri::command]
+pub async fn get_server_process_tree(state: State<'_, AppState>) -> Result<Vec<ProcessNode>, AppError> {
+    use sysinfo::{Pid, System};
+
+    let server_pid = state.server.lock().await.as_ref().and_then(|h| h.pid());
+    let roots = match server_pid {
+        Some(pid) => vec![pid],
+        None => process::listening_pids(state.last_server_port.load(Ordering::SeqCst)).await,
+    };
+
+    let mut system = System::new();
+    system.refresh_processes();
+    Ok(roots
+        .into_iter()
+        .filter_map(|pid| process_node(&system, Pid::from_u32(pid), 0))
+        .collect())
+}
+
+fn process_node(system: &sysinfo::System, pid: sysinfo::Pid, depth: usize) -> Option<ProcessNode> {
+    let process = system.process(pid)?;
+    let mut children: Vec<ProcessNode> = if depth < MAX_PROCESS_TREE_DEPTH {
+        system
+            .processes()
+            .iter()
+            .filter(|(_, p)| p.parent() == Some(pid))
+            .filter_map(|(child, _)| process_node(system, *child, depth + 1))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    children.sort_by_key(|node| node.pid);
+
+    Some(ProcessNode {
+        pid: pid.as_u32(),
+        name: process.name().to_string(),
+        memory_mb: process.memory() as f64 / (1024.0 * 1024.0),
+        children,
+    })
+}
+
+#[derive(serde::Serialize)]
+pub struct SystemInfo {
+    pub os_name: String,
+    pub os_version: String,
+    pub arch: String,
+    pub total_memory_mb: u64,
+    pub available_memory_mb: u64,
+    /// Logical CPUs
+    pub cpu_count: u32,
+    pub app_version: String,
+    /// Package manager of the source checkout, when running from one
+    pub workspace_tool: Option<server::WorkspaceTool>,
+}
+
+#[derive(serde::Serialize)]
+pub struct AppMemoryUsage {
+    pub tauri_process_bytes: u64,
+    pub server_process_bytes: Option<u64>,
+    pub webview_process_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(serde::Serialize)]
+pub struct NodeInfo {
+    pub path: std::path::PathBuf,
+    pub version: String,
+}
+
 #[derive(serde::Serialize)]
 pub struct EnvironmentInfo {
     pub node: CommandInfo,
@@ -172,3 +1405,181 @@ async fn check_command_version(command: &str, args: &[&str]) -> CommandInfo {
         },
     }
 }
+
+// Window commands
+/// Save a PNG of the main window and return its path. Windows only; other
+/// platforms get an error.
+#[tauri::command]
+pub async fn capture_screenshot(app: tauri::AppHandle) -> Result<String, AppError> {
+    let path = window::capture_screenshot(&app)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Bundle logs, config and system details for a bug report as
+/// `diagnostics_<timestamp>.zip` in `output_path`. Returns the zip's size
+/// in bytes.
+#[tauri::command]
+pub async fn generate_diagnostics(state: State<'_, AppState>, output_path: PathBuf) -> Result<u64, AppError> {
+    let path = diagnostics::generate(&state, &output_path).await?;
+    let size = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    Ok(size)
+}
+
+/// Save the server's current status, config, logs, history, metrics and
+/// resource usage as a JSON snapshot and return its path.
+#[tauri::command]
+pub async fn create_server_snapshot(state: State<'_, AppState>) -> Result<String, AppError> {
+    let path = snapshot::create(&state).await?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Saved snapshots, newest first.
+#[tauri::command]
+pub async fn list_snapshots(app: tauri::AppHandle) -> Result<Vec<snapshot::SnapshotInfo>, AppError> {
+    snapshot::list(&app)
+}
+
+#[tauri::command]
+pub async fn delete_snapshot(app: tauri::AppHandle, id: String) -> Result<(), AppError> {
+    snapshot::delete(&app, &id)
+}
+
+#[tauri::command]
+pub async fn get_config(state: State<'_, AppState>) -> Result<Config, AppError> {
+    Ok(state.config.lock().await.clone())
+}
+
+/// Apply `patch` and save the result. Server settings take effect the next
+/// time the server starts.
+#[tauri::command]
+pub async fn update_config(
+    state: State<'_, AppState>,
+    patch: ConfigPatch,
+) -> Result<Config, AppError> {
+    let app = &state.app_handle;
+    let mut config = state.config.lock().await;
+    let mut updated = config.clone();
+    updated.apply(patch);
+    if let Some(pattern) = &updated.access_log_pattern {
+        regex::Regex::new(pattern)
+            .map_err(|e| AppError::InvalidArgument(format!("Invalid access log pattern: {}", e)))?;
+    }
+    updated.save(app)?;
+    *config = updated.clone();
+    Ok(updated)
+}
+
+/// Restore factory settings, backing up the current file first, and emit
+/// `config-reset`. Refused while the server or tunnel runs, since they were
+/// started with the old settings.
+#[tauri::command]
+pub async fn reset_config_to_defaults(state: State<'_, AppState>) -> Result<Config, AppError> {
+    let app = &state.app_handle;
+    // Held until the reset is done so neither can start in the meantime
+    let server_state = state.server.lock().await;
+    let tunnel_state = state.tunnel.lock().await;
+    if server_state.is_some() || tunnel_state.is_some() {
+        return Err(AppError::ServerRunning);
+    }
+
+    let defaults = Config::reset(app)?;
+    *state.config.lock().await = defaults.clone();
+    let _ = app.emit("config-reset", &defaults);
+    Ok(defaults)
+}
+
+/// Save the current settings as JSON at `path`, with credential-like
+/// server environment values blanked.
+#[tauri::command]
+pub async fn export_config(state: State<'_, AppState>, path: PathBuf) -> Result<(), AppError> {
+    state.config.lock().await.export(&path)
+}
+
+/// Replace the settings with a file written by `export_config`, save them
+/// as `config.toml` and emit `config-imported`. The allowed scripts are
+/// kept as they are.
+#[tauri::command]
+pub async fn import_config(state: State<'_, AppState>, path: PathBuf) -> Result<Config, AppError> {
+    let app = &state.app_handle;
+    let mut config = state.config.lock().await;
+    let imported = Config::import(&path, &config)?;
+    imported.save(app)?;
+    *config = imported.clone();
+    drop(config);
+    tracing::info!("Imported settings from {}", path.display());
+    let _ = app.emit("config-imported", &imported);
+    Ok(imported)
+}
+
+// Navigation commands
+#[tauri::command]
+pub async fn navigate_back(state: State<'_, AppState>) -> Result<(), AppError> {
+    let app = &state.app_handle;
+    let event = {
+        let mut history = state.navigation.lock()
+            .map_err(|_| "Navigation history is unavailable".to_string())?;
+        history.back().ok_or("No previous page")?;
+        window::NavigationEvent::from(&*history)
+    };
+    window::navigate(app, &event.url)?;
+    app.emit("app://navigation", event)
+        .map_err(|e| format!("Failed to emit navigation event: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn navigate_forward(state: State<'_, AppState>) -> Result<(), AppError> {
+    let app = &state.app_handle;
+    let event = {
+        let mut history = state.navigation.lock()
+            .map_err(|_| "Navigation history is unavailable".to_string())?;
+        history.forward().ok_or("No next page")?;
+        window::NavigationEvent::from(&*history)
+    };
+    window::navigate(app, &event.url)?;
+    app.emit("app://navigation", event)
+        .map_err(|e| format!("Failed to emit navigation event: {}", e))?;
+    Ok(())
+}
+
+/// Record an in-app navigation (e.g. a client-side route change) that
+/// does not trigger a page load.
+#[tauri::command]
+pub async fn push_navigation(
+    state: State<'_, AppState>,
+    url: String,
+) -> Result<(), AppError> {
+    let app = &state.app_handle;
+    let event = {
+        let mut history = state.navigation.lock()
+            .map_err(|_| "Navigation history is unavailable".to_string())?;
+        history.push(url);
+        window::NavigationEvent::from(&*history)
+    };
+    app.emit("app://navigation", event)
+        .map_err(|e| format!("Failed to emit navigation event: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn can_go_back(state: State<'_, AppState>) -> Result<bool, AppError> {
+    let history = state.navigation.lock()
+        .map_err(|_| "Navigation history is unavailable".to_string())?;
+    Ok(history.can_go_back())
+}
+
+#[tauri::command]
+pub async fn can_go_forward(state: State<'_, AppState>) -> Result<bool, AppError> {
+    let history = state.navigation.lock()
+        .map_err(|_| "Navigation history is unavailable".to_string())?;
+    Ok(history.can_go_forward())
+}
+
+#[tauri::command]
+pub async fn show_keyboard_shortcuts(app: tauri::AppHandle) -> Result<(), AppError> {
+    app.emit("app://show-shortcuts", shortcuts::KEYBOARD_SHORTCUTS)
+        .map_err(|e| format!("Failed to emit shortcuts event: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,474 @@
+use crate::error::AppError;
+use crate::log_rotate;
+use crate::server::{self, HealthCheckMethod, ServerConfig, ServerConfigBuilder, Severity};
+use crate::tunnel::TunnelProvider;
+use crate::AppState;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{Emitter, Manager};
+
+const CONFIG_FILE: &str = "config.toml";
+/// How often `ConfigWatcher` looks at the file; a change is reloaded once
+/// it has stayed the same for one interval
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+/// `validate_config` fields that depend on the machine rather than the
+/// file, left for `start` to report
+const MACHINE_FIELDS: &[&str] = &["workingDir", "script"];
+
+/// Schema version written to `config_version`. Bump it and add a step to
+/// `MIGRATIONS` when a key is renamed or changes meaning; new keys with a
+/// default need no migration.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// One schema upgrade, applied to the raw TOML before it is deserialized
+type Migration = fn(toml::Value) -> Result<toml::Value, String>;
+
+/// `MIGRATIONS[n]` upgrades a version `n` config to version `n + 1`.
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+/// User preferences persisted as `app_data_dir()/config.toml`. Missing
+/// keys fall back to their defaults, so older files keep loading.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Schema version this file was written with; missing means 0
+    pub config_version: u32,
+    pub server_port: u16,
+    pub auto_start_server: bool,
+    pub auto_start_tunnel: bool,
+    pub node_binary_path: Option<PathBuf>,
+    pub server_log_capacity: usize,
+    /// Size at which `logs/server.log` is rotated
+    pub server_log_max_bytes: u64,
+    /// Rotated server logs kept next to it
+    pub server_log_backups: usize,
+    pub server_startup_timeout_secs: u64,
+    pub max_server_restarts: u8,
+    pub preferred_tunnel_provider: TunnelProvider,
+    pub tunnel_subdomain: Option<String>,
+    /// Extra environment variables for the server, set by `update_server_env`
+    pub server_env: HashMap<String, String>,
+    /// `error`, `warn`, `info`, `debug` or `trace`; `SIDE_LOG` overrides it
+    pub log_level: String,
+    /// Desktop notifications for server restarts and the tunnel URL
+    pub notifications_enabled: bool,
+    /// package.json scripts `exec_in_server_dir` may run. Only editable in
+    /// the file itself, so the frontend can't widen its own permissions;
+    /// `export` leaves it out and `import` ignores it.
+    pub allowed_scripts: Vec<String>,
+    /// Limit for scripts run by `exec_in_server_dir`
+    pub script_timeout_secs: u64,
+    /// Content security policy for the webview, replacing the one in
+    /// tauri.conf.json; read once at launch, so a change applies on the next
+    /// start. Only editable in the file itself, like `allowed_scripts`.
+    pub csp: Option<String>,
+    /// Regex for the server log lines that count as one request in
+    /// `get_requests_per_minute`; unset uses `metrics::DEFAULT_ACCESS_LOG_PATTERN`
+    pub access_log_pattern: Option<String>,
+    /// Path of the server's health endpoint; `/health` by default
+    pub health_check_path: String,
+    pub health_check_method: HealthCheckMethod,
+    /// Text a healthy response body must contain, if any
+    pub health_check_expected_body: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let server = ServerConfig::default();
+        Config {
+            config_version: CONFIG_VERSION,
+            server_port: server.port,
+            auto_start_server: true,
+            auto_start_tunnel: false,
+            node_binary_path: None,
+            server_log_capacity: server::DEFAULT_LOG_CAPACITY,
+            server_log_max_bytes: log_rotate::DEFAULT_MAX_SIZE_BYTES,
+            server_log_backups: log_rotate::DEFAULT_MAX_BACKUPS,
+            server_startup_timeout_secs: server.startup_timeout.as_secs(),
+            max_server_restarts: server.max_restarts as u8,
+            preferred_tunnel_provider: TunnelProvider::default(),
+            tunnel_subdomain: None,
+            server_env: HashMap::new(),
+            log_level: crate::logging::DEFAULT_LOG_LEVEL.to_string(),
+            notifications_enabled: true,
+            allowed_scripts: Vec::new(),
+            script_timeout_secs: server::DEFAULT_SCRIPT_TIMEOUT.as_secs(),
+            csp: None,
+            access_log_pattern: None,
+            health_check_path: server.health_check.path,
+            health_check_method: server.health_check.method,
+            health_check_expected_body: None,
+        }
+    }
+}
+
+/// A partial update from the frontend; only the fields that are set change.
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigPatch {
+    pub server_port: Option<u16>,
+    pub auto_start_server: Option<bool>,
+    pub auto_start_tunnel: Option<bool>,
+    pub node_binary_path: Option<PathBuf>,
+    pub server_log_capacity: Option<usize>,
+    pub server_startup_timeout_secs: Option<u64>,
+    pub max_server_restarts: Option<u8>,
+    pub preferred_tunnel_provider: Option<TunnelProvider>,
+    pub tunnel_subdomain: Option<String>,
+    pub notifications_enabled: Option<bool>,
+    pub script_timeout_secs: Option<u64>,
+    pub access_log_pattern: Option<String>,
+    pub health_check_path: Option<String>,
+    pub health_check_method: Option<HealthCheckMethod>,
+    pub health_check_expected_body: Option<String>,
+}
+
+impl Config {
+    /// Read the saved config, upgrading it from older schema versions, or
+    /// the defaults if none has been saved yet. A file that can't be read
+    /// as a config is copied to `config.toml.bak.<timestamp>` and the
+    /// defaults are used instead.
+    pub fn load(app: &tauri::AppHandle) -> Result<Config, AppError> {
+        let path = config_path(app)?;
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        };
+
+        match Config::parse(&content) {
+            Ok(config) => Ok(config),
+            Err(e) => {
+                let backup = backup_config_file(&path)?;
+                tracing::warn!(
+                    "Using default config, {} is invalid ({}); saved a copy as {}",
+                    path.display(),
+                    e,
+                    backup.display()
+                );
+                Ok(Config::default())
+            }
+        }
+    }
+
+    /// A config file's contents, upgraded from older schema versions.
+    fn parse(content: &str) -> Result<Config, String> {
+        let mut config = toml::from_str::<toml::Value>(content)
+            .map_err(|e| e.to_string())
+            .and_then(migrate_config)
+            .and_then(|value| value.try_into::<Config>().map_err(|e| e.to_string()))?;
+        config.config_version = config.config_version.max(CONFIG_VERSION);
+        Ok(config)
+    }
+
+    /// Problems that parsing doesn't catch: an access log pattern that
+    /// isn't a regex, or server settings `validate_config` calls errors.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(pattern) = &self.access_log_pattern {
+            regex::Regex::new(pattern).map_err(|e| format!("access_log_pattern: {}", e))?;
+        }
+        let errors: Vec<String> = server::validate_config(&self.server_config().build())
+            .into_iter()
+            .filter(|w| w.severity == Severity::Error && !MACHINE_FIELDS.contains(&w.field.as_str()))
+            .map(|w| format!("{}: {}", w.field, w.message))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// The `csp` key of the saved config, for use before the app (and with
+    /// it `app_data_dir()`) exists. Anything unreadable counts as unset;
+    /// `load` reports it once logging is up.
+    pub fn saved_csp(identifier: &str) -> Option<String> {
+        // Where `app_data_dir()` resolves to
+        let path = dirs::data_dir()?.join(identifier).join(CONFIG_FILE);
+        let content = std::fs::read_to_string(path).ok()?;
+        let value = toml::from_str::<toml::Value>(&content).ok()?;
+        Some(value.get("csp")?.as_str()?.to_string())
+    }
+
+    pub fn save(&self, app: &tauri::AppHandle) -> Result<(), AppError> {
+        let path = config_path(app)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| AppError::ConfigParseError(e.to_string()))?;
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// Write this config to `path` as JSON, for moving settings to another
+    /// machine. Server environment variables that look like credentials
+    /// are exported with empty values, and `allowed_scripts` not at all.
+    pub fn export(&self, path: &Path) -> Result<(), AppError> {
+        let mut exported = self.clone();
+        for (name, value) in exported.server_env.iter_mut() {
+            if server::is_sensitive_env(name) {
+                value.clear();
+            }
+        }
+        let mut json = serde_json::to_value(&exported)
+            .map_err(|e| AppError::ConfigParseError(e.to_string()))?;
+        if let Some(object) = json.as_object_mut() {
+            object.remove("allowed_scripts");
+            object.remove("csp");
+        }
+        let content = serde_json::to_string_pretty(&json)
+            .map_err(|e| AppError::ConfigParseError(e.to_string()))?;
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// Read a config written by `export`, upgrading it from older schema
+    /// versions. Unlike `load`, an invalid file is an error, as is one from
+    /// a newer version of the app. `allowed_scripts` is taken from
+    /// `current`, never from the file.
+    pub fn import(path: &Path, current: &Config) -> Result<Config, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let invalid = |e: String| AppError::ConfigParseError(format!("{}: {}", path.display(), e));
+
+        let mut json: serde_json::Value = serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        // TOML has no null; an unset optional key is simply absent
+        if let Some(object) = json.as_object_mut() {
+            object.retain(|key, value| !value.is_null() && key != "allowed_scripts" && key != "csp");
+        }
+        if let Some(version) = json.get("config_version").and_then(|v| v.as_u64()) {
+            if version > CONFIG_VERSION as u64 {
+                return Err(invalid(format!(
+                    "config_version {} is from a newer version of the app (this one reads up to {})",
+                    version, CONFIG_VERSION
+                )));
+            }
+        }
+
+        let value = toml::Value::try_from(json).map_err(|e| invalid(e.to_string()))?;
+        let mut config: Config = migrate_config(value)
+            .and_then(|value| value.try_into().map_err(|e: toml::de::Error| e.to_string()))
+            .map_err(invalid)?;
+        config.config_version = CONFIG_VERSION;
+        config.allowed_scripts = current.allowed_scripts.clone();
+        config.csp = current.csp.clone();
+        Ok(config)
+    }
+
+    /// Replace the saved config with the defaults, keeping a timestamped
+    /// copy of the old file.
+    pub fn reset(app: &tauri::AppHandle) -> Result<Config, AppError> {
+        let path = config_path(app)?;
+        if path.exists() {
+            let backup = backup_config_file(&path)?;
+            tracing::info!("Saved the old config as {}", backup.display());
+        }
+        let config = Config::default();
+        config.save(app)?;
+        Ok(config)
+    }
+
+    pub fn apply(&mut self, patch: ConfigPatch) {
+        if let Some(port) = patch.server_port {
+            self.server_port = port;
+        }
+        if let Some(enabled) = patch.auto_start_server {
+            self.auto_start_server = enabled;
+        }
+        if let Some(enabled) = patch.auto_start_tunnel {
+            self.auto_start_tunnel = enabled;
+        }
+        if let Some(path) = patch.node_binary_path {
+            // An empty path clears the override
+            self.node_binary_path = (!path.as_os_str().is_empty()).then_some(path);
+        }
+        if let Some(capacity) = patch.server_log_capacity {
+            self.server_log_capacity = capacity;
+        }
+        if let Some(secs) = patch.server_startup_timeout_secs {
+            self.server_startup_timeout_secs = secs;
+        }
+        if let Some(restarts) = patch.max_server_restarts {
+            self.max_server_restarts = restarts;
+        }
+        if let Some(provider) = patch.preferred_tunnel_provider {
+            self.preferred_tunnel_provider = provider;
+        }
+        if let Some(subdomain) = patch.tunnel_subdomain {
+            self.tunnel_subdomain = (!subdomain.is_empty()).then_some(subdomain);
+        }
+        if let Some(enabled) = patch.notifications_enabled {
+            self.notifications_enabled = enabled;
+        }
+        if let Some(secs) = patch.script_timeout_secs {
+            self.script_timeout_secs = secs;
+        }
+        if let Some(pattern) = patch.access_log_pattern {
+            self.access_log_pattern = (!pattern.is_empty()).then_some(pattern);
+        }
+        if let Some(path) = patch.health_check_path {
+            // An empty path goes back to the default
+            self.health_check_path = if path.is_empty() { server::DEFAULT_HEALTH_CHECK_PATH.to_string() } else { path };
+        }
+        if let Some(method) = patch.health_check_method {
+            self.health_check_method = method;
+        }
+        if let Some(text) = patch.health_check_expected_body {
+            self.health_check_expected_body = (!text.is_empty()).then_some(text);
+        }
+    }
+
+    /// Server settings from this config, for per-start overrides to go on top of.
+    pub fn server_config(&self) -> ServerConfigBuilder {
+        let mut builder = ServerConfig::builder()
+            .port(self.server_port)
+            .log_capacity(self.server_log_capacity)
+            .startup_timeout(Duration::from_secs(self.server_startup_timeout_secs))
+            .max_restarts(self.max_server_restarts as u32)
+            .env(self.server_env.clone())
+            .health_check_path(&self.health_check_path)
+            .health_check_method(self.health_check_method)
+            .health_check_expected_body(self.health_check_expected_body.clone());
+        if let Some(node) = &self.node_binary_path {
+            builder = builder.node_binary(node);
+        }
+        builder
+    }
+}
+
+/// Bring a parsed config up to `CONFIG_VERSION`. Files from a newer
+/// version are left alone; keys this version doesn't know are ignored.
+pub fn migrate_config(mut value: toml::Value) -> Result<toml::Value, String> {
+    let version = match value.get("config_version") {
+        None => 0,
+        Some(toml::Value::Integer(version)) if *version >= 0 => *version as u32,
+        Some(other) => return Err(format!("config_version must be a whole number, not {}", other)),
+    };
+
+    for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        value = migrate(value).map_err(|e| format!("Migrating config from version {}: {}", from, e))?;
+    }
+    Ok(value)
+}
+
+/// Version 0 is every file written before the config was versioned. All
+/// of its keys still mean the same thing, so only the version is added.
+fn migrate_v0_to_v1(mut value: toml::Value) -> Result<toml::Value, String> {
+    let table = value.as_table_mut().ok_or("expected a table at the top level")?;
+    table.insert("config_version".to_string(), toml::Value::Integer(1));
+    Ok(value)
+}
+
+/// Copy `path` to `<path>.bak.<unix timestamp>` and return the copy's path.
+pub fn backup_config_file(path: &std::path::Path) -> Result<PathBuf, AppError> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".bak.{}", chrono::Utc::now().timestamp()));
+    let backup = PathBuf::from(backup);
+    std::fs::copy(path, &backup)
+        .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    Ok(backup)
+}
+
+/// Keys whose values differ between `old` and `new`, sorted.
+fn changed_fields(old: &Config, new: &Config) -> Vec<String> {
+    let table = |config: &Config| match toml::Value::try_from(config) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => toml::map::Map::new(),
+    };
+    let (old, new) = (table(old), table(new));
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect()
+}
+
+/// Reloads `config.toml` when it is edited outside the app, emitting
+/// `config-reloaded` with the changed keys or `config-reload-error` (and
+/// keeping the current config) if the new file is invalid. A deleted file
+/// means the defaults. Polls the file's modification time, since `notify`
+/// isn't available to this build. Stops when dropped, so it is kept in
+/// managed state.
+pub struct ConfigWatcher {
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+/// What the watcher compares between polls; `None` when there is no file
+type FileStamp = Option<(SystemTime, u64)>;
+
+impl ConfigWatcher {
+    pub fn start(app: tauri::AppHandle) -> Result<ConfigWatcher, AppError> {
+        let path = config_path(&app)?;
+        let task = tauri::async_runtime::spawn(async move {
+            let mut seen = file_stamp(&path);
+            let mut settling: Option<FileStamp> = None;
+            loop {
+                tokio::time::sleep(WATCH_INTERVAL).await;
+                let stamp = file_stamp(&path);
+                if stamp == seen {
+                    settling = None;
+                } else if settling == Some(stamp) {
+                    seen = stamp;
+                    settling = None;
+                    reload(&app, &path).await;
+                } else {
+                    // Still being written, or changed again
+                    settling = Some(stamp);
+                }
+            }
+        });
+        Ok(ConfigWatcher { task })
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn file_stamp(path: &Path) -> FileStamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+async fn reload(app: &tauri::AppHandle, path: &Path) {
+    let reloaded = match std::fs::read_to_string(path) {
+        Ok(content) => Config::parse(&content).and_then(|config| config.validate().map(|()| config)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let reloaded = match reloaded {
+        Ok(config) => config,
+        Err(message) => {
+            tracing::warn!("Keeping the current config, {} is invalid: {}", path.display(), message);
+            let _ = app.emit("config-reload-error", serde_json::json!({ "message": message }));
+            return;
+        }
+    };
+
+    let state = app.state::<AppState>();
+    let changed = {
+        let mut config = state.config.lock().await;
+        let changed = changed_fields(&config, &reloaded);
+        *config = reloaded;
+        changed
+    };
+    // The app's own saves change the file without changing anything
+    if changed.is_empty() {
+        return;
+    }
+    tracing::info!("Reloaded {} ({})", path.display(), changed.join(", "));
+    let _ = app.emit("config-reloaded", serde_json::json!({ "changed_fields": changed }));
+}
+
+fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    app.path().app_data_dir()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e).into())
+}
@@ -0,0 +1,346 @@
+use crate::error::AppError;
+use crate::{commands, logging, server, snapshot, window, AppState};
+use chrono::{Datelike, Local, Timelike};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Log lines included from the end of each log
+const LOG_TAIL_LINES: usize = 1000;
+/// Restart events included, newest last
+const RESTART_EVENTS: usize = 5;
+/// Newest server snapshots included
+const SNAPSHOTS: usize = 5;
+/// Stands in for anything `redact_sensitive` removes
+const REDACTED: &str = "[REDACTED]";
+
+/// Collect what a bug report needs into `diagnostics_<timestamp>.zip` in
+/// `output_dir` and return the zip's path: the config, the end of the
+/// server and tunnel logs, system info, the Node.js version, the last few
+/// restarts, the newest server snapshots and a screenshot of the main
+/// window. Every text file is passed through `redact_sensitive`. Anything
+/// that can't be collected is listed in `missing.txt` instead of failing
+/// the whole bundle.
+pub async fn generate(state: &AppState, output_dir: &Path) -> Result<PathBuf, AppError> {
+    let app = &state.app_handle;
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut missing = Vec::new();
+    let mut add_text = |name: String, content: Result<String, String>| match content {
+        Ok(content) => files.push((name, redact_sensitive(&content).into_bytes())),
+        Err(e) => missing.push(format!("{}: {}", name, e)),
+    };
+
+    let config = state.config.lock().await.clone();
+    add_text("config.toml".to_string(), toml::to_string_pretty(&config).map_err(|e| e.to_string()));
+
+    let server_log = match state.server_logs.file() {
+        Some(file) => {
+            let path = file.lock().await.path().to_path_buf();
+            read_tail(&path, |_| true)
+        }
+        None => Err("the server log file isn't open".to_string()),
+    };
+    add_text("server.log".to_string(), server_log);
+
+    // Tunnel output has no file of its own; it is in the app log under the
+    // `tunnel` target
+    let tunnel_log = logging::log_dir(app)
+        .ok()
+        .and_then(|dir| logging::latest_log_file(&dir))
+        .ok_or_else(|| "no app log file has been written".to_string())
+        .and_then(|path| read_tail(&path, is_tunnel_line));
+    add_text("tunnel.log".to_string(), tunnel_log);
+
+    let system_info = commands::get_system_info(app.clone())
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|info| serde_json::to_string_pretty(&info).map_err(|e| e.to_string()));
+    add_text("system_info.json".to_string(), system_info);
+
+    let node_version = match server::resolve_node_binary(config.node_binary_path.as_deref()) {
+        Ok(path) => server::program_version(&path)
+            .await
+            .map(|version| format!("{}\n{}\n", path.display(), version))
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    add_text("node_version.txt".to_string(), node_version);
+
+    let events = state.server_history.events();
+    let recent = &events[events.len().saturating_sub(RESTART_EVENTS)..];
+    add_text("restarts.json".to_string(), serde_json::to_string_pretty(recent).map_err(|e| e.to_string()));
+
+    match snapshot::newest_files(app, SNAPSHOTS) {
+        Ok(snapshots) => {
+            for (name, path) in snapshots {
+                let content = std::fs::read_to_string(&path).map_err(|e| e.to_string());
+                add_text(format!("snapshots/{}", name), content);
+            }
+        }
+        Err(e) => add_text("snapshots".to_string(), Err(e.to_string())),
+    }
+
+    match window::capture_main_window_png(app) {
+        Ok(png) => files.push(("screenshot.png".to_string(), png)),
+        Err(e) => missing.push(format!("screenshot.png: {}", e)),
+    }
+    if !missing.is_empty() {
+        files.push(("missing.txt".to_string(), format!("{}\n", missing.join("\n")).into_bytes()));
+    }
+
+    let mut zip = ZipWriter::default();
+    for (name, data) in &files {
+        zip.add(name, data)
+            .map_err(|e| format!("Failed to compress {}: {}", name, e))?;
+    }
+
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+    let path = output_dir.join(format!("diagnostics_{}.zip", Local::now().format("%Y%m%d_%H%M%S")));
+    std::fs::write(&path, zip.finish())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    tracing::info!("Wrote diagnostics to {}", path.display());
+    Ok(path)
+}
+
+/// Replace the value of every `key = value`, `"key": value` or `KEY=value`
+/// pair whose key looks like a credential (see `server::is_sensitive_env`)
+/// with `[REDACTED]`. Pairs are recognised as a whole line (TOML, JSON,
+/// `.env`) or as a single word inside one (a logged command line).
+pub fn redact_sensitive(content: &str) -> String {
+    let mut redacted: Vec<String> = content.lines().map(redact_line).collect();
+    if content.ends_with('\n') {
+        redacted.push(String::new());
+    }
+    redacted.join("\n")
+}
+
+fn redact_line(line: &str) -> String {
+    if let Some(at) = line.find(['=', ':']) {
+        let key = line[..at].trim().trim_start_matches("export ").trim_matches('"');
+        let value = line[at + 1..].trim();
+        if !value.is_empty() && !key.contains(char::is_whitespace) && server::is_sensitive_env(key) {
+            // Keep the line valid in the format it came from
+            let quoted = if value.starts_with('"') { format!("\"{}\"", REDACTED) } else { REDACTED.to_string() };
+            let comma = if value.ends_with(',') { "," } else { "" };
+            let space = if line[at + 1..].starts_with(' ') { " " } else { "" };
+            return format!("{}{}{}{}", &line[..=at], space, quoted, comma);
+        }
+    }
+
+    line.split(' ')
+        .map(|word| match word.split_once('=') {
+            Some((name, value)) if !value.is_empty() && server::is_sensitive_env(name.trim_start_matches('-')) => {
+                format!("{}={}", name, REDACTED)
+            }
+            _ => word.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The last `LOG_TAIL_LINES` lines of `path` that `keep` accepts.
+fn read_tail(path: &Path, keep: impl Fn(&str) -> bool) -> Result<String, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let lines: Vec<&str> = content.lines().filter(|line| keep(line)).collect();
+    let tail = &lines[lines.len().saturating_sub(LOG_TAIL_LINES)..];
+    Ok(format!("{}\n", tail.join("\n")))
+}
+
+/// Whether an app log line (`<time> <level> <target>: <message>`) came
+/// from the tunnel process.
+fn is_tunnel_line(line: &str) -> bool {
+    line.split_once(": ")
+        .is_some_and(|(prefix, _)| prefix.ends_with(" tunnel"))
+}
+
+/// Builds a zip archive in memory. Entries are deflated and written one
+/// after another, followed by the central directory in `finish`.
+#[derive(Default)]
+struct ZipWriter {
+    out: Vec<u8>,
+    central_directory: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    // Version 2.0 of the format: deflate, no ZIP64
+    const VERSION: u16 = 20;
+    // Bit 11: names are UTF-8
+    const FLAGS: u16 = 1 << 11;
+    const DEFLATE: u16 = 8;
+
+    fn add(&mut self, name: &str, data: &[u8]) -> std::io::Result<()> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+
+        let crc = crc32fast::hash(data);
+        let (time, date) = dos_timestamp();
+        let offset = self.out.len() as u32;
+
+        let header = &mut self.out;
+        put_u32(header, 0x0403_4b50);
+        put_u16(header, Self::VERSION);
+        put_u16(header, Self::FLAGS);
+        put_u16(header, Self::DEFLATE);
+        put_u16(header, time);
+        put_u16(header, date);
+        put_u32(header, crc);
+        put_u32(header, compressed.len() as u32);
+        put_u32(header, data.len() as u32);
+        put_u16(header, name.len() as u16);
+        put_u16(header, 0);
+        header.extend_from_slice(name.as_bytes());
+        header.extend_from_slice(&compressed);
+
+        let entry = &mut self.central_directory;
+        put_u32(entry, 0x0201_4b50);
+        put_u16(entry, Self::VERSION);
+        put_u16(entry, Self::VERSION);
+        put_u16(entry, Self::FLAGS);
+        put_u16(entry, Self::DEFLATE);
+        put_u16(entry, time);
+        put_u16(entry, date);
+        put_u32(entry, crc);
+        put_u32(entry, compressed.len() as u32);
+        put_u32(entry, data.len() as u32);
+        put_u16(entry, name.len() as u16);
+        // Extra field, comment, disk number, internal and external attributes
+        put_u16(entry, 0);
+        put_u16(entry, 0);
+        put_u16(entry, 0);
+        put_u16(entry, 0);
+        put_u32(entry, 0);
+        put_u32(entry, offset);
+        entry.extend_from_slice(name.as_bytes());
+
+        self.entries += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.out.len() as u32;
+        let directory_size = self.central_directory.len() as u32;
+        self.out.append(&mut self.central_directory);
+
+        let end = &mut self.out;
+        put_u32(end, 0x0605_4b50);
+        put_u16(end, 0);
+        put_u16(end, 0);
+        put_u16(end, self.entries);
+        put_u16(end, self.entries);
+        put_u32(end, directory_size);
+        put_u32(end, directory_offset);
+        put_u16(end, 0);
+        self.out
+    }
+}
+
+/// The current local time as MS-DOS `(time, date)`, which zip entries use.
+fn dos_timestamp() -> (u16, u16) {
+    let now = Local::now();
+    let time = (now.hour() << 11) | (now.minute() << 5) | (now.second() / 2);
+    let date = ((now.year().max(1980) - 1980) as u32) << 9 | (now.month() << 5) | now.day();
+    (time as u16, date as u16)
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read as _;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn zip_with_two_entries() {
+        let first = b"hello hello hello hello".to_vec();
+        let mut zip = ZipWriter::default();
+        zip.add("first.txt", &first).unwrap();
+        zip.add("second.txt", b"second").unwrap();
+        let archive = zip.finish();
+
+        // End of central directory record: the last 22 bytes, no comment
+        let end = archive.len() - 22;
+        assert_eq!(u32_at(&archive, end), 0x0605_4b50);
+        assert_eq!(u16_at(&archive, end + 8), 2);
+        assert_eq!(u16_at(&archive, end + 10), 2);
+        let directory_size = u32_at(&archive, end + 12) as usize;
+        let directory_offset = u32_at(&archive, end + 16) as usize;
+        assert_eq!(directory_offset + directory_size, end);
+
+        // The first central directory entry points back at the first local
+        // header, at the start of the archive
+        assert_eq!(u32_at(&archive, directory_offset), 0x0201_4b50);
+        assert_eq!(u32_at(&archive, directory_offset + 42), 0);
+        let name_len = u16_at(&archive, directory_offset + 28) as usize;
+        assert_eq!(&archive[directory_offset + 46..directory_offset + 46 + name_len], b"first.txt");
+
+        assert_eq!(u32_at(&archive, 0), 0x0403_4b50);
+        assert_eq!(u16_at(&archive, 8), ZipWriter::DEFLATE);
+        assert_eq!(u32_at(&archive, 14), crc32fast::hash(&first));
+        let compressed_len = u32_at(&archive, 18) as usize;
+        assert_eq!(u32_at(&archive, 22) as usize, first.len());
+        let data_start = 30 + u16_at(&archive, 26) as usize + u16_at(&archive, 28) as usize;
+
+        let mut inflated = Vec::new();
+        flate2::read::DeflateDecoder::new(&archive[data_start..data_start + compressed_len])
+            .read_to_end(&mut inflated)
+            .unwrap();
+        assert_eq!(inflated, first);
+
+        // The second entry starts right after the first one's data
+        let second_header = data_start + compressed_len;
+        assert_eq!(u32_at(&archive, second_header), 0x0403_4b50);
+        let second_entry = directory_offset + 46 + name_len;
+        assert_eq!(u32_at(&archive, second_entry + 42) as usize, second_header);
+    }
+
+    #[test]
+    fn redacts_toml() {
+        assert_eq!(
+            redact_sensitive("port = 8787\napi_token = \"abc123\"\n"),
+            "port = 8787\napi_token = \"[REDACTED]\"\n"
+        );
+    }
+
+    #[test]
+    fn redacts_json() {
+        assert_eq!(
+            redact_sensitive("{\n  \"GITHUB_TOKEN\": \"abc123\",\n  \"PORT\": \"8787\"\n}"),
+            "{\n  \"GITHUB_TOKEN\": \"[REDACTED]\",\n  \"PORT\": \"8787\"\n}"
+        );
+    }
+
+    #[test]
+    fn redacts_dotenv() {
+        assert_eq!(
+            redact_sensitive("DB_PASSWORD=hunter2\nexport SECRET_KEY=abc\nNODE_ENV=production"),
+            "DB_PASSWORD=[REDACTED]\nexport SECRET_KEY=[REDACTED]\nNODE_ENV=production"
+        );
+    }
+
+    #[test]
+    fn redacts_command_line() {
+        assert_eq!(
+            redact_sensitive("Starting node dist/index.js --api-key=abc123 --port=8787 AUTH_TOKEN=xyz"),
+            "Starting node dist/index.js --api-key=[REDACTED] --port=8787 AUTH_TOKEN=[REDACTED]"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Name of the file read from the server's working directory
+pub const ENV_FILE: &str = ".env";
+
+/// Keys a `.env` file may not set for the server. Compared ignoring case,
+/// as Windows does.
+const PROTECTED_KEYS: &[&str] = &["PATH", "HOME", "SYSTEMROOT"];
+
+/// Parse a dotenv file: `KEY=value` lines, optionally prefixed with
+/// `export`, with `#` comments. Double-quoted values understand `\n`, `\t`,
+/// `\"` and `\\`; single-quoted values are taken literally.
+pub fn load_env_file(path: &Path) -> Result<HashMap<String, String>, AppError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut vars = HashMap::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = parse_line(line).map_err(|reason| {
+            AppError::ConfigParseError(format!("{} line {}: {}", path.display(), index + 1, reason))
+        })?;
+        vars.insert(key, value);
+    }
+    Ok(vars)
+}
+
+/// Variables from the `.env` file in `dir` that the server should get.
+/// Protected keys and anything in `explicit` (the `ServerConfig::env`) are
+/// left out so they win. No file means no variables.
+pub fn server_env_file(dir: &Path, explicit: &HashMap<String, String>) -> Result<HashMap<String, String>, AppError> {
+    let path = dir.join(ENV_FILE);
+    if !path.is_file() {
+        return Ok(HashMap::new());
+    }
+
+    let mut vars = load_env_file(&path)?;
+    vars.retain(|key, _| {
+        !explicit.contains_key(key) && !PROTECTED_KEYS.iter().any(|p| p.eq_ignore_ascii_case(key))
+    });
+    tracing::info!("Loaded {} variables from {}", vars.len(), path.display());
+    Ok(vars)
+}
+
+/// Whether `key` can be set through `add_server_env_var`: letters, digits
+/// and underscores, not starting with a digit (`^[A-Z_][A-Z0-9_]*$`,
+/// ignoring case).
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_line(line: &str) -> Result<(String, String), String> {
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (key, value) = line.split_once('=').ok_or("expected KEY=value")?;
+
+    let key = key.trim();
+    let valid_key = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if !valid_key {
+        return Err(format!("invalid key '{}'", key));
+    }
+
+    Ok((key.to_string(), parse_value(value.trim())?))
+}
+
+fn parse_value(value: &str) -> Result<String, String> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        let (inner, _) = rest.split_once('\'').ok_or("unterminated single quote")?;
+        return Ok(inner.to_string());
+    }
+
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut parsed = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(parsed),
+                '\\' => match chars.next() {
+                    Some('n') => parsed.push('\n'),
+                    Some('t') => parsed.push('\t'),
+                    Some(other) => parsed.push(other),
+                    None => break,
+                },
+                _ => parsed.push(c),
+            }
+        }
+        return Err("unterminated double quote".to_string());
+    }
+
+    // Unquoted: a ` #` starts a trailing comment
+    let value = match value.find(" #") {
+        Some(at) => &value[..at],
+        None => value,
+    };
+    Ok(value.trim_end().to_string())
+}
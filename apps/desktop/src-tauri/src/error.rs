@@ -0,0 +1,95 @@
+use serde::ser::SerializeStruct;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Errors returned to the frontend. Serialized as
+/// `{ "kind": "ServerNotRunning", "message": "Server is not running" }` so
+/// the UI can branch on `kind` and still show `message` as-is.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Node.js not found. Searched: {}", display_paths(.searched_paths))]
+    NodeNotFound { searched_paths: Vec<PathBuf> },
+    #[error("Server is already running")]
+    ServerAlreadyRunning,
+    #[error("Server is not running")]
+    ServerNotRunning,
+    #[error("Tunnel is already running")]
+    TunnelAlreadyRunning,
+    #[error("Tunnel is not running")]
+    TunnelNotRunning,
+    /// An operation that needs both stopped found the server or the tunnel
+    /// running
+    #[error("The server or tunnel is running; stop them first")]
+    ServerRunning,
+    #[error("Failed to start process: {0}")]
+    ProcessSpawnFailed(#[from] std::io::Error),
+    #[error("Health check at {url} failed after {attempts} attempts: {last_error}")]
+    HealthCheckFailed { url: String, attempts: u8, last_error: String },
+    #[error("Invalid configuration: {0}")]
+    ConfigParseError(String),
+    /// A command argument outside the range it accepts
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("Server on port {port} did not become ready within {}s", .after.as_secs())]
+    ServerNotReady { port: u16, after: Duration },
+    #[error("Server stdin was not captured; start it with captureStdin")]
+    StdinNotCaptured,
+    #[error("No internet connection")]
+    NoInternetConnection,
+    #[error("{operation} timed out after {}s", .after.as_secs())]
+    Timeout { operation: String, after: Duration },
+    /// Anything without a more specific variant
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AppError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::NodeNotFound { .. } => "NodeNotFound",
+            AppError::ServerAlreadyRunning => "ServerAlreadyRunning",
+            AppError::ServerNotRunning => "ServerNotRunning",
+            AppError::TunnelAlreadyRunning => "TunnelAlreadyRunning",
+            AppError::TunnelNotRunning => "TunnelNotRunning",
+            AppError::ServerRunning => "ServerRunning",
+            AppError::ProcessSpawnFailed(_) => "ProcessSpawnFailed",
+            AppError::HealthCheckFailed { .. } => "HealthCheckFailed",
+            AppError::ConfigParseError(_) => "ConfigParseError",
+            AppError::InvalidArgument(_) => "InvalidArgument",
+            AppError::ServerNotReady { .. } => "ServerNotReady",
+            AppError::StdinNotCaptured => "StdinNotCaptured",
+            AppError::NoInternetConnection => "NoInternetConnection",
+            AppError::Timeout { .. } => "Timeout",
+            AppError::Other(_) => "Other",
+        }
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+impl serde::Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
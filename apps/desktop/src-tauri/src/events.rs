@@ -0,0 +1,26 @@
+use crate::commands::{ServerStatus, TunnelStatus};
+use crate::tray::{self, ServiceState};
+use crate::window;
+use tauri::Emitter;
+
+/// Emitted with a `ServerStatus` whenever the server starts, stops, crashes
+/// or is restarted, so the frontend doesn't have to poll
+/// `get_server_status`.
+pub const SERVER_STATE_EVENT: &str = "server:state";
+/// Emitted with a `TunnelStatus` whenever the tunnel connects, reconnects or
+/// goes away.
+pub const TUNNEL_STATE_EVENT: &str = "tunnel:state";
+
+/// Also updates the tray and the window title.
+pub fn emit_server_changed(app: &tauri::AppHandle, status: &ServerStatus) {
+    let _ = app.emit(SERVER_STATE_EVENT, status);
+    tray::set_server_state(app, if status.running { ServiceState::Running } else { ServiceState::Stopped });
+    window::update_title_status(app, |title| title.server = status.clone());
+}
+
+/// Also updates the tray and the window title.
+pub fn emit_tunnel_changed(app: &tauri::AppHandle, status: &TunnelStatus) {
+    let _ = app.emit(TUNNEL_STATE_EVENT, status);
+    tray::set_tunnel_state(app, if status.running { ServiceState::Running } else { ServiceState::Stopped });
+    window::update_title_status(app, |title| title.tunnel = status.clone());
+}
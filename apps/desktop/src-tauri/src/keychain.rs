@@ -0,0 +1,175 @@
+use crate::error::AppError;
+
+/// Service name the secrets are filed under
+const SERVICE: &str = "side-desktop";
+
+/// Save `secret` in the OS credential store under `account`, replacing any
+/// earlier one: Credential Manager on Windows, the login keychain on macOS
+/// and the Secret Service (through `secret-tool`) elsewhere.
+pub async fn set_secret(account: &str, secret: &str) -> Result<(), AppError> {
+    platform::set(account, secret)
+        .await
+        .map_err(|e| format!("Failed to save {} to the keychain: {}", account, e).into())
+}
+
+/// The secret saved under `account`, or `None` if there is none.
+pub async fn get_secret(account: &str) -> Result<Option<String>, AppError> {
+    platform::get(account)
+        .await
+        .map_err(|e| format!("Failed to read {} from the keychain: {}", account, e).into())
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::SERVICE;
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_NOT_FOUND};
+    use windows_sys::Win32::Security::Credentials::{
+        CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    fn target_name(account: &str) -> Vec<u16> {
+        format!("{}:{}", SERVICE, account).encode_utf16().chain(Some(0)).collect()
+    }
+
+    pub async fn set(account: &str, secret: &str) -> Result<(), String> {
+        let mut target = target_name(account);
+        let mut blob = secret.as_bytes().to_vec();
+        // SAFETY: every pointer in `credential` points into `target` or
+        // `blob`, which outlive the call; CredWriteW copies what it keeps
+        unsafe {
+            let mut credential: CREDENTIALW = std::mem::zeroed();
+            credential.Type = CRED_TYPE_GENERIC;
+            credential.TargetName = target.as_mut_ptr();
+            credential.CredentialBlobSize = blob.len() as u32;
+            credential.CredentialBlob = blob.as_mut_ptr();
+            credential.Persist = CRED_PERSIST_LOCAL_MACHINE;
+            if CredWriteW(&credential, 0) == 0 {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get(account: &str) -> Result<Option<String>, String> {
+        let target = target_name(account);
+        // SAFETY: CredReadW either fails or hands back a credential that
+        // stays valid until the CredFree below
+        unsafe {
+            let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) == 0 {
+                if GetLastError() == ERROR_NOT_FOUND {
+                    return Ok(None);
+                }
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+            let blob = std::slice::from_raw_parts(
+                (*credential).CredentialBlob,
+                (*credential).CredentialBlobSize as usize,
+            );
+            let secret = String::from_utf8_lossy(blob).into_owned();
+            CredFree(credential as *const _);
+            Ok(Some(secret))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::SERVICE;
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+    use tokio::process::Command;
+
+    /// `security` exits with this when the item doesn't exist
+    const ITEM_NOT_FOUND: i32 = 44;
+
+    pub async fn set(account: &str, secret: &str) -> Result<(), String> {
+        // Sent through `security -i` on stdin so the secret never shows up
+        // in a process listing
+        let line = format!(
+            "add-generic-password -U -s {} -a {} -w {}\n",
+            quote(SERVICE),
+            quote(account),
+            quote(secret)
+        );
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(line.as_bytes()).await.map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Interactive mode exits 0 even when the command failed
+        if !output.status.success() || !stderr.trim().is_empty() {
+            return Err(stderr.trim().to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn get(account: &str) -> Result<Option<String>, String> {
+        let output = Command::new("security")
+            .args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"])
+            .output()
+            .await
+            .map_err(|e| e.to_string())?;
+        match output.status.code() {
+            Some(0) => Ok(Some(String::from_utf8_lossy(&output.stdout).trim_end_matches('\n').to_string())),
+            Some(ITEM_NOT_FOUND) => Ok(None),
+            _ => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        }
+    }
+
+    /// A double-quoted word for `security -i`, which splits on spaces.
+    fn quote(word: &str) -> String {
+        format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use super::SERVICE;
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+    use tokio::process::Command;
+
+    pub async fn set(account: &str, secret: &str) -> Result<(), String> {
+        // secret-tool reads the secret from stdin
+        let mut child = Command::new("secret-tool")
+            .arg("store")
+            .arg(format!("--label=S-IDE {}", account))
+            .args(["service", SERVICE, "account", account])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{} (is libsecret's secret-tool installed?)", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(secret.as_bytes()).await.map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn get(account: &str) -> Result<Option<String>, String> {
+        let output = Command::new("secret-tool")
+            .args(["lookup", "service", SERVICE, "account", account])
+            .output()
+            .await
+            .map_err(|e| format!("{} (is libsecret's secret-tool installed?)", e))?;
+        // A missing item is a failed lookup with nothing on stderr
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() && !stderr.trim().is_empty() {
+            return Err(stderr.trim().to_string());
+        }
+        let secret = String::from_utf8_lossy(&output.stdout).into_owned();
+        Ok((!secret.is_empty()).then_some(secret))
+    }
+}
@@ -0,0 +1,191 @@
+mod commands;
+mod config;
+mod diagnostics;
+mod env;
+pub mod error;
+mod events;
+mod keychain;
+mod log_rotate;
+mod logging;
+mod metrics;
+mod notification;
+mod paths;
+mod process;
+pub mod server;
+mod shortcuts;
+mod snapshot;
+mod tray;
+mod tunnel;
+mod window;
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU16;
+use tauri::Manager;
+use tokio::sync::Mutex as TokioMutex;
+
+/// State shared by all commands, registered as a single managed state.
+///
+/// When more than one lock is needed, take them in field order
+/// (`server`, then `tunnel`, then `share_links`) to avoid deadlocks.
+/// `config` is only ever held briefly and never while waiting on another
+/// lock.
+struct AppState {
+    server: TokioMutex<Option<server::ServerHandle>>,
+    server_logs: server::LogBuffer,
+    server_history: server::RestartHistory,
+    server_circuit: server::CircuitBreaker,
+    /// Port of the most recently started server, kept after it stops
+    last_server_port: AtomicU16,
+    tunnel: TokioMutex<Option<tunnel::TunnelHandle>>,
+    tunnel_history: server::RestartHistory,
+    share_links: TokioMutex<HashMap<String, commands::ShareLink>>,
+    // Plain mutex: it is also updated from the synchronous page load hook
+    navigation: std::sync::Mutex<window::NavigationHistory>,
+    /// What the main window's title currently shows
+    title_status: std::sync::Mutex<window::TitleStatus>,
+    config: TokioMutex<config::Config>,
+    /// For commands and background tasks that emit events or reach other
+    /// managed state
+    app_handle: tauri::AppHandle,
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let mut context = tauri::generate_context!();
+    // Tauri applies the policy from the context to every page it serves
+    if let Some(csp) = config::Config::saved_csp(&context.config().identifier) {
+        context.config_mut().app.security.csp = Some(tauri::utils::config::Csp::Policy(csp));
+    }
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .on_page_load(window::on_page_load)
+        .setup(|app| {
+            let loaded = config::Config::load(app.handle());
+            let log_level = loaded.as_ref().map_or(logging::DEFAULT_LOG_LEVEL, |c| &c.log_level);
+            let log_dir = logging::log_dir(app.handle())
+                .inspect_err(|e| eprintln!("Logging to stderr only: {}", e))
+                .ok();
+            logging::init(logging::resolve_level(log_level), log_dir.clone());
+            let config = loaded.unwrap_or_else(|e| {
+                tracing::warn!("Using default config: {}", e);
+                config::Config::default()
+            });
+            match app.path().resource_dir() {
+                Ok(dir) => server::set_resource_dir(dir),
+                Err(e) => tracing::error!("Failed to resolve resource dir: {}", e),
+            }
+            match app.path().app_data_dir() {
+                Ok(dir) => server::set_app_data_dir(dir),
+                Err(e) => tracing::error!("Failed to resolve app data dir: {}", e),
+            }
+            let server_logs = server::LogBuffer::new(server::DEFAULT_LOG_CAPACITY);
+            if let Some(dir) = &log_dir {
+                let path = dir.join(log_rotate::SERVER_LOG_FILE);
+                match log_rotate::LogRotator::open(&path, config.server_log_max_bytes, config.server_log_backups) {
+                    Ok(rotator) => server_logs.attach_file(rotator),
+                    Err(e) => tracing::error!("Failed to open {}: {}", path.display(), e),
+                }
+            }
+            // Managed here rather than on the builder so it can hold the handle
+            app.manage(AppState {
+                server: TokioMutex::new(None),
+                server_logs,
+                server_history: server::RestartHistory::default(),
+                server_circuit: server::CircuitBreaker::default(),
+                last_server_port: AtomicU16::new(config.server_port),
+                tunnel: TokioMutex::new(None),
+                tunnel_history: server::RestartHistory::default(),
+                share_links: TokioMutex::new(HashMap::new()),
+                navigation: std::sync::Mutex::new(window::NavigationHistory::default()),
+                title_status: std::sync::Mutex::new(window::TitleStatus::default()),
+                config: TokioMutex::new(config),
+                app_handle: app.handle().clone(),
+            });
+            match config::ConfigWatcher::start(app.handle().clone()) {
+                Ok(watcher) => {
+                    app.manage(watcher);
+                }
+                Err(e) => tracing::error!("Config file changes won't be picked up: {}", e),
+            }
+            window::setup(app)?;
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            commands::start_server,
+            commands::validate_server_config,
+            commands::stop_server,
+            commands::restart_server,
+            commands::update_server_env,
+            commands::add_server_env_var,
+            commands::remove_server_env_var,
+            commands::get_server_env_var,
+            commands::get_server_status,
+            commands::get_server_uptime,
+            commands::get_server_pid,
+            commands::get_server_history,
+            commands::reset_circuit_breaker,
+            commands::send_server_stdin,
+            commands::get_server_logs,
+            commands::subscribe_server_logs,
+            commands::export_logs,
+            commands::clear_server_logs,
+            commands::get_app_data_dir,
+            commands::get_log_file_path,
+            commands::open_log_directory,
+            commands::open_server_directory,
+            commands::reveal_file_in_manager,
+            commands::benchmark_server,
+            commands::get_engine_requirements,
+            commands::install_server_dependencies,
+            commands::exec_in_server_dir,
+            commands::check_dependencies,
+            commands::start_tunnel,
+            commands::network_check,
+            commands::stop_tunnel,
+            commands::stop_all,
+            commands::get_tunnel_status,
+            commands::get_tunnel_stats,
+            commands::secure_store_tunnel_token,
+            commands::get_tunnel_token,
+            commands::get_combined_status,
+            commands::get_tunnel_providers,
+            commands::create_tunnel_share_link,
+            commands::revoke_share_link,
+            commands::resolve_share_link,
+            commands::list_share_links,
+            commands::check_environment,
+            commands::check_node_installed,
+            commands::get_node_version,
+            commands::get_node_info,
+            commands::check_port,
+            commands::get_available_ports,
+            commands::get_app_memory_usage,
+            commands::get_system_info,
+            commands::list_running_node_processes,
+            commands::kill_process,
+            commands::get_server_process_tree,
+            commands::get_server_metrics,
+            commands::get_health_check_config,
+            commands::disable_watchdog_temporarily,
+            commands::get_requests_per_minute,
+            commands::capture_screenshot,
+            commands::generate_diagnostics,
+            commands::create_server_snapshot,
+            commands::list_snapshots,
+            commands::delete_snapshot,
+            commands::show_keyboard_shortcuts,
+            commands::get_config,
+            commands::update_config,
+            commands::reset_config_to_defaults,
+            commands::export_config,
+            commands::import_config,
+            commands::navigate_back,
+            commands::navigate_forward,
+            commands::push_navigation,
+            commands::can_go_back,
+            commands::can_go_forward,
+        ])
+        .run(context)
+        .expect("error while running tauri application");
+}
@@ -0,0 +1,100 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::fs::File;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Size at which the server log is rotated
+pub const DEFAULT_MAX_SIZE_BYTES: u64 = 10 * 1024 * 1024;
+/// Rotated files kept as `server.log.1` (newest) to `server.log.5`
+pub const DEFAULT_MAX_BACKUPS: usize = 5;
+/// Name of the server log inside the log directory
+pub const SERVER_LOG_FILE: &str = "server.log";
+
+/// Appends to a log file, moving it aside once it grows past
+/// `max_size_bytes`: `server.log` becomes `server.log.1`, older backups
+/// shift up one, and anything past `max_backups` is dropped.
+pub struct LogRotator {
+    path: PathBuf,
+    max_size_bytes: u64,
+    max_backups: usize,
+    file: File,
+    size: u64,
+}
+
+impl LogRotator {
+    /// Open (or create) `path` for appending.
+    pub fn open(path: impl Into<PathBuf>, max_size_bytes: u64, max_backups: usize) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(LogRotator { path, max_size_bytes, max_backups, file: File::from_std(file), size })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flush and empty the current file. Backups are left alone.
+    pub async fn truncate(&mut self) -> io::Result<()> {
+        self.flush().await?;
+        self.file.set_len(0).await?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn backup_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    // Renames are quick enough to do inline, even from poll_write
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_backups == 0 {
+            let _ = std::fs::remove_file(&self.path);
+        } else {
+            for n in (1..self.max_backups).rev() {
+                let from = self.backup_path(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.backup_path(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.backup_path(1))?;
+        }
+        self.file = File::from_std(open_append(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl AsyncWrite for LogRotator {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        // The previous write took the file over the limit; everything it
+        // wrote has to land before the file is moved
+        if this.size >= this.max_size_bytes {
+            ready!(Pin::new(&mut this.file).poll_flush(cx))?;
+            this.rotate()?;
+        }
+        let written = ready!(Pin::new(&mut this.file).poll_write(cx, buf))?;
+        this.size += written as u64;
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_shutdown(cx)
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<std::fs::File> {
+    std::fs::OpenOptions::new().create(true).append(true).open(path)
+}
@@ -0,0 +1,177 @@
+use chrono::{Local, NaiveDate};
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{span, Event, Metadata, Subscriber};
+
+/// Overrides `Config::log_level`, e.g. `SIDE_LOG=debug`
+pub const LOG_LEVEL_ENV: &str = "SIDE_LOG";
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+/// Log files are named `side-desktop.YYYY-MM-DD.log`, one per day
+const LOG_FILE_PREFIX: &str = "side-desktop.";
+/// Daily files kept in the log directory; older ones are removed on rotation
+const LOG_FILES_KEPT: usize = 7;
+
+/// Directory the log files are written to, `app_data_dir()/logs`.
+pub fn log_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    use tauri::Manager;
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("logs"))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+/// Level from `SIDE_LOG` if set, otherwise `configured`. Unknown names fall
+/// back to `info`.
+pub fn resolve_level(configured: &str) -> LevelFilter {
+    let wanted = std::env::var(LOG_LEVEL_ENV).unwrap_or_else(|_| configured.to_string());
+    LevelFilter::from_str(wanted.trim()).unwrap_or(LevelFilter::INFO)
+}
+
+/// Install the global subscriber: every event goes to stderr and, when
+/// `dir` is given and writable, to a daily log file in it.
+pub fn init(level: LevelFilter, dir: Option<PathBuf>) {
+    let file = dir.and_then(|dir| match std::fs::create_dir_all(&dir) {
+        Ok(()) => Some(Mutex::new(DailyFile { dir, current: None })),
+        Err(e) => {
+            eprintln!("Logging to stderr only, failed to create {}: {}", dir.display(), e);
+            None
+        }
+    });
+    let logger = Logger { level, file };
+    if tracing::subscriber::set_global_default(logger).is_err() {
+        eprintln!("A tracing subscriber is already installed");
+    }
+}
+
+struct Logger {
+    level: LevelFilter,
+    file: Option<Mutex<DailyFile>>,
+}
+
+impl Subscriber for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.level >= *metadata.level()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.level)
+    }
+
+    // Spans aren't used here; every span shares one id and is ignored
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let now = Local::now();
+        let metadata = event.metadata();
+        let mut line = format!(
+            "{} {:>5} {}: ",
+            now.format("%Y-%m-%dT%H:%M:%S%.3f"),
+            metadata.level(),
+            metadata.target()
+        );
+        event.record(&mut FieldWriter(&mut line));
+        line.push('\n');
+
+        let _ = std::io::stderr().write_all(line.as_bytes());
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                file.write(now.date_naive(), &line);
+            }
+        }
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+/// Appends an event's message, then any other fields as `name=value`.
+struct FieldWriter<'a>(&'a mut String);
+
+impl Visit for FieldWriter<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.push_str(value);
+        } else {
+            let _ = write!(self.0, " {}={}", field.name(), value);
+        }
+    }
+}
+
+/// The log file for the current day, reopened when the date changes.
+struct DailyFile {
+    dir: PathBuf,
+    current: Option<(NaiveDate, File)>,
+}
+
+impl DailyFile {
+    fn write(&mut self, date: NaiveDate, line: &str) {
+        if self.current.as_ref().is_none_or(|(day, _)| *day != date) {
+            let path = self.dir.join(format!("{}{}.log", LOG_FILE_PREFIX, date.format("%Y-%m-%d")));
+            // A failed open is retried on the next event
+            self.current = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .ok()
+                .map(|file| (date, file));
+            remove_old_logs(&self.dir);
+        }
+        if let Some((_, file)) = &mut self.current {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
+
+/// The newest daily log file in `dir`, if any has been written.
+pub fn latest_log_file(dir: &Path) -> Option<PathBuf> {
+    log_files(dir).pop()
+}
+
+/// Daily log files in `dir`, oldest first. The date in the name sorts the
+/// same as the files' age.
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut logs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX) && name.ends_with(".log"))
+        })
+        .collect();
+    logs.sort();
+    logs
+}
+
+/// Delete all but the newest `LOG_FILES_KEPT` log files.
+fn remove_old_logs(dir: &Path) {
+    let logs = log_files(dir);
+    let excess = logs.len().saturating_sub(LOG_FILES_KEPT);
+    for old in &logs[..excess] {
+        let _ = std::fs::remove_file(old);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::server::LogLine;
+use regex::Regex;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Path fetched when `ServerConfig::metrics_path` is unset
+pub const DEFAULT_METRICS_PATH: &str = "/metrics";
+const METRICS_REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Counters read from the server's metrics endpoint. Anything the endpoint
+/// doesn't report is 0.
+#[derive(Default, serde::Serialize)]
+pub struct ServerMetrics {
+    pub requests_total: u64,
+    pub errors_total: u64,
+    pub uptime_secs: u64,
+    pub memory_bytes: u64,
+}
+
+/// Matches the request line most Node.js access loggers (morgan, pino-http,
+/// Express and Next.js dev output) print, e.g. `GET /api/items 200`
+pub const DEFAULT_ACCESS_LOG_PATTERN: &str = r"\b(GET|POST|PUT|PATCH|DELETE|HEAD|OPTIONS)\s+/\S*";
+/// One bucket per minute over the last hour
+const RPM_BUCKETS: usize = 60;
+const MINUTE_MS: u64 = 60_000;
+
+/// Request rate counted from the server's access log lines, from
+/// `requests_per_minute`.
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpmMetrics {
+    /// Requests in the last 60 seconds
+    pub current_rpm: u32,
+    pub peak_rpm_last_hour: u32,
+    pub avg_rpm_last_hour: f32,
+    /// Requests in each of the last 60 minutes, oldest first; the last one
+    /// is `current_rpm`
+    pub buckets_per_minute: Vec<u32>,
+}
+
+/// Count the `lines` matching `pattern` in one-minute buckets ending at
+/// `now_ms`. Lines older than an hour, or from the future, are ignored.
+pub fn requests_per_minute(lines: &[LogLine], pattern: &Regex, now_ms: u64) -> RpmMetrics {
+    let mut buckets = vec![0u32; RPM_BUCKETS];
+    for line in lines {
+        let Some(age) = now_ms.checked_sub(line.timestamp_ms) else {
+            continue;
+        };
+        let minutes_ago = (age / MINUTE_MS) as usize;
+        if minutes_ago < RPM_BUCKETS && pattern.is_match(&line.message) {
+            buckets[RPM_BUCKETS - 1 - minutes_ago] += 1;
+        }
+    }
+    RpmMetrics {
+        current_rpm: buckets[RPM_BUCKETS - 1],
+        peak_rpm_last_hour: buckets.iter().copied().max().unwrap_or(0),
+        avg_rpm_last_hour: buckets.iter().sum::<u32>() as f32 / RPM_BUCKETS as f32,
+        buckets_per_minute: buckets,
+    }
+}
+
+/// JSON keys read for each field, first match wins
+const JSON_REQUESTS: &[&str] = &["requests_total", "requestsTotal", "requests"];
+const JSON_ERRORS: &[&str] = &["errors_total", "errorsTotal", "errors"];
+const JSON_UPTIME: &[&str] = &["uptime_secs", "uptimeSecs", "uptime"];
+const JSON_MEMORY: &[&str] = &["memory_bytes", "memoryBytes", "memory"];
+
+/// GET `path` from the server on `port` and read it as JSON or Prometheus
+/// text. A missing endpoint, or one that answers with neither, gives all
+/// zeros rather than an error.
+pub async fn fetch(port: u16, path: &str) -> ServerMetrics {
+    let url = format!("http://localhost:{}/{}", port, path.trim_start_matches('/'));
+    let body = async {
+        let client = reqwest::Client::builder().timeout(METRICS_REQUEST_TIMEOUT).build()?;
+        client.get(&url).send().await?.error_for_status()?.text().await
+    };
+    match body.await {
+        Ok(body) => parse(&body),
+        Err(e) => {
+            tracing::debug!("No metrics from {}: {}", url, e);
+            ServerMetrics::default()
+        }
+    }
+}
+
+fn parse(body: &str) -> ServerMetrics {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(fields)) => parse_json(&fields),
+        _ => parse_prometheus(body),
+    }
+}
+
+/// A flat JSON object such as `{ "requests_total": 10, "uptime": 42.5 }`.
+fn parse_json(fields: &serde_json::Map<String, serde_json::Value>) -> ServerMetrics {
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| fields.get(*key)?.as_f64())
+            .map_or(0, |value| value.max(0.0) as u64)
+    };
+    ServerMetrics {
+        requests_total: field(JSON_REQUESTS),
+        errors_total: field(JSON_ERRORS),
+        uptime_secs: field(JSON_UPTIME),
+        memory_bytes: field(JSON_MEMORY),
+    }
+}
+
+/// Prometheus text format. Requests and errors are the sums of every
+/// counter whose name ends in `requests_total` or `errors_total` (across
+/// all label sets); uptime and memory come from the standard `process_*`
+/// metrics that Node.js clients such as prom-client export.
+fn parse_prometheus(body: &str) -> ServerMetrics {
+    let mut types: HashMap<&str, &str> = HashMap::new();
+    let mut samples: Vec<(&str, f64)> = Vec::new();
+    for line in body.lines().map(str::trim) {
+        if let Some(declaration) = line.strip_prefix("# TYPE ") {
+            let mut parts = declaration.split_whitespace();
+            if let (Some(name), Some(kind)) = (parts.next(), parts.next()) {
+                types.insert(name, kind);
+            }
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // `name{labels} value [timestamp]`; label values may contain spaces
+        let (name, rest) = match line.find(['{', ' ']) {
+            Some(at) if line[at..].starts_with('{') => match line[at..].find('}') {
+                Some(close) => (&line[..at], &line[at + close + 1..]),
+                None => continue,
+            },
+            Some(at) => (&line[..at], &line[at..]),
+            None => continue,
+        };
+        if let Some(value) = rest.split_whitespace().next().and_then(|v| v.parse::<f64>().ok()) {
+            samples.push((name, value));
+        }
+    }
+
+    // Undeclared metrics are taken at their word
+    let is_counter = |name: &str| types.get(name).is_none_or(|kind| *kind == "counter");
+    let sum = |suffix: &str| -> u64 {
+        samples
+            .iter()
+            .filter(|(name, _)| name.ends_with(suffix) && is_counter(name))
+            .map(|(_, value)| value.max(0.0))
+            .sum::<f64>() as u64
+    };
+    let gauge = |name: &str| samples.iter().find(|(n, _)| *n == name).map(|(_, value)| *value);
+
+    let uptime_secs = match (gauge("process_uptime_seconds"), gauge("process_start_time_seconds")) {
+        (Some(uptime), _) => uptime.max(0.0) as u64,
+        (None, Some(started)) => (chrono::Utc::now().timestamp() as f64 - started).max(0.0) as u64,
+        (None, None) => 0,
+    };
+    ServerMetrics {
+        requests_total: sum("requests_total"),
+        errors_total: sum("errors_total"),
+        uptime_secs,
+        memory_bytes: gauge("process_resident_memory_bytes").map_or(0, |bytes| bytes.max(0.0) as u64),
+    }
+}
@@ -0,0 +1,77 @@
+use crate::AppState;
+use tauri::Manager;
+use tokio::process::Command;
+
+/// Show a desktop notification unless `Config::notifications_enabled` is
+/// off. Runs in the background; a notification that can't be shown is
+/// only logged.
+///
+/// Sent through the platform's own tool: a WinRT toast via PowerShell on
+/// Windows, `osascript` on macOS and `notify-send` elsewhere. Title and body
+/// are passed as arguments or environment variables, never spliced into a
+/// script.
+pub fn show(app: &tauri::AppHandle, title: impl Into<String>, body: impl Into<String>) {
+    let app = app.clone();
+    let (title, body) = (title.into(), body.into());
+    tauri::async_runtime::spawn(async move {
+        if !app.state::<AppState>().config.lock().await.notifications_enabled {
+            return;
+        }
+        let mut command = notify_command(&app, &title, &body);
+        match command.output().await {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => tracing::warn!(
+                "Notification '{}' failed: {}",
+                title,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => tracing::warn!("Notification '{}' failed: {}", title, e),
+        }
+    });
+}
+
+/// Shows a toast as the app's AppUserModelID, which the installer registers
+#[cfg(windows)]
+const TOAST_SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null
+$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $template.GetElementsByTagName('text')
+$text.Item(0).AppendChild($template.CreateTextNode($env:SIDE_NOTIFY_TITLE)) > $null
+$text.Item(1).AppendChild($template.CreateTextNode($env:SIDE_NOTIFY_BODY)) > $null
+$toast = [Windows.UI.Notifications.ToastNotification]::new($template)
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:SIDE_NOTIFY_APP_ID).Show($toast)
+"#;
+
+#[cfg(windows)]
+fn notify_command(app: &tauri::AppHandle, title: &str, body: &str) -> Command {
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", TOAST_SCRIPT])
+        .env("SIDE_NOTIFY_TITLE", title)
+        .env("SIDE_NOTIFY_BODY", body)
+        .env("SIDE_NOTIFY_APP_ID", &app.config().identifier);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn notify_command(_app: &tauri::AppHandle, title: &str, body: &str) -> Command {
+    let mut command = Command::new("osascript");
+    command
+        .args([
+            "-e", "on run argv",
+            "-e", "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e", "end run",
+        ])
+        .args([title, body]);
+    command
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn notify_command(app: &tauri::AppHandle, title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command
+        .arg(format!("--app-name={}", app.package_info().name))
+        .arg("--")
+        .args([title, body]);
+    command
+}
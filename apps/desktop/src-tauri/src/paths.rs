@@ -0,0 +1,135 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// How many parent directories are searched for a checkout's files
+pub const DEV_SEARCH_DEPTH: usize = 10;
+
+/// What marks a JS workspace root for `find_project_root`. A `package.json`
+/// only counts if it has a `workspaces` field; the first one seen is still
+/// used as a fallback when nothing better turns up.
+pub const DEFAULT_ROOT_MARKERS: &[&str] = &[
+    "pnpm-workspace.yaml",
+    "turbo.json",
+    "lerna.json",
+    "nx.json",
+    "yarn.lock",
+    "package-lock.json",
+    "package.json",
+];
+
+/// Result of the first successful `find_project_root`. A failed search
+/// isn't cached, so it is retried next time.
+static PROJECT_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Root of the source checkout, using `DEFAULT_ROOT_MARKERS`. Only the
+/// first call searches the filesystem.
+pub fn find_project_root() -> Result<PathBuf, String> {
+    if let Some(root) = PROJECT_ROOT.get() {
+        return Ok(root.clone());
+    }
+    let root = find_project_root_with(DEFAULT_ROOT_MARKERS)?;
+    Ok(PROJECT_ROOT.get_or_init(|| root).clone())
+}
+
+/// Directory the server runs from: `apps/server` in the checkout for debug
+/// builds, the bundled `resources/server` otherwise.
+pub fn find_server_directory() -> Result<PathBuf, String> {
+    #[cfg(debug_assertions)]
+    {
+        let server_dir = find_project_root()?.join("apps").join("server");
+        if server_dir.join("package.json").exists() {
+            Ok(server_dir)
+        } else {
+            Err("Could not find server directory. Please run from the project root.".to_string())
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    {
+        // Production: the server is bundled as resources/server/dist/index.js
+        Ok(crate::server::resource_dir().join("resources").join("server"))
+    }
+}
+
+/// Root of the source checkout: `SIDE_PROJECT_ROOT` if set, otherwise the
+/// nearest workspace root above the current directory or, failing that,
+/// above the executable.
+pub fn find_project_root_with(marker_files: &[&str]) -> Result<PathBuf, String> {
+    // Explicit override for layouts the search below gets wrong
+    if let Ok(root) = std::env::var("SIDE_PROJECT_ROOT") {
+        let root = PathBuf::from(root);
+        if root.is_dir() {
+            return Ok(root);
+        }
+        return Err(format!(
+            "SIDE_PROJECT_ROOT points to a missing directory: {}",
+            root.display()
+        ));
+    }
+
+    let current_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current dir: {}", e))?;
+    if let Some(root) = search_workspace_root(&current_dir, marker_files) {
+        return Ok(root);
+    }
+
+    // In dev the exe is in target/debug, below the project root
+    let exe_path = std::env::current_exe()
+        .map_err(|e| format!("Failed to get exe path: {}", e))?;
+    if let Some(root) = exe_path.parent().and_then(|dir| search_workspace_root(dir, marker_files)) {
+        return Ok(root);
+    }
+
+    Err("Could not find project root (package.json)".to_string())
+}
+
+/// Walk up from `start` looking for a workspace root, and again from its
+/// canonical path if that finds nothing: when `start` is reached through a
+/// symlink its logical parents aren't the checkout's.
+fn search_workspace_root(start: &Path, marker_files: &[&str]) -> Option<PathBuf> {
+    if let Some(root) = search_ancestors(start, marker_files) {
+        return Some(root);
+    }
+    let resolved = std::fs::canonicalize(start).ok()?;
+    if resolved == start {
+        return None;
+    }
+    search_ancestors(&resolved, marker_files)
+}
+
+/// A directory with a marker (or a `package.json` declaring `workspaces`)
+/// wins; otherwise the first `package.json` seen is used, if `package.json`
+/// is a marker at all.
+fn search_ancestors(start: &Path, marker_files: &[&str]) -> Option<PathBuf> {
+    let accept_package_json = marker_files.contains(&"package.json");
+    let mut first_package_dir = None;
+
+    for dir in start.ancestors().take(DEV_SEARCH_DEPTH) {
+        let has_marker = marker_files
+            .iter()
+            .filter(|m| **m != "package.json")
+            .any(|m| dir.join(m).exists());
+        if has_marker {
+            return Some(dir.to_path_buf());
+        }
+
+        let package_json = dir.join("package.json");
+        if accept_package_json && package_json.exists() {
+            if declares_workspaces(&package_json) {
+                return Some(dir.to_path_buf());
+            }
+            first_package_dir.get_or_insert_with(|| dir.to_path_buf());
+        }
+    }
+
+    first_package_dir
+}
+
+/// Whether the `package.json` at `path` has a `workspaces` field (npm and
+/// Yarn workspaces).
+fn declares_workspaces(path: &Path) -> bool {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .is_some_and(|json| json.get("workspaces").is_some())
+}
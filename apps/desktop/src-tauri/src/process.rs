@@ -0,0 +1,280 @@
+use crate::error::AppError;
+use tokio::process::{Child, Command};
+
+/// A spawned child together with everything it spawns, so stopping it
+/// doesn't leave workers behind (still holding the server port, say).
+///
+/// On Unix the child leads its own process group and the whole group is
+/// signalled. On Windows it is assigned to a Job Object that kills every
+/// process in it when the job is terminated or its handle is closed, which
+/// also happens when the `ProcessGroup` is dropped. Anything the child
+/// spawns before it is assigned to the job (a few instructions after
+/// `spawn` returns) is not covered.
+pub struct ProcessGroup {
+    #[cfg(unix)]
+    pgid: libc::pid_t,
+    #[cfg(windows)]
+    job: JobHandle,
+}
+
+/// Resource caps for a child. Limits are inherited, so they also cover
+/// whatever the child spawns (the `node` started by `npm run dev`, say).
+#[derive(Clone, Copy, Default)]
+pub struct ProcessLimits {
+    /// Address space on Unix (`RLIMIT_AS`), committed memory per process on
+    /// Windows. Node.js reserves far more address space than it uses, so on
+    /// Unix this needs to be generous.
+    pub max_memory_mb: Option<u64>,
+    /// `RLIMIT_NOFILE`; Unix only
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pub max_open_files: Option<u64>,
+}
+
+impl ProcessLimits {
+    /// Have `command`'s child apply these limits to itself before it runs.
+    /// The Windows memory limit is set on the job in `ProcessGroup::attach`
+    /// instead.
+    pub fn apply(self, command: &mut Command) {
+        #[cfg(unix)]
+        if self.max_memory_mb.is_some() || self.max_open_files.is_some() {
+            // SAFETY: the hook only calls setrlimit, which is safe to call
+            // between fork and exec
+            unsafe {
+                command.pre_exec(move || self.set_rlimits());
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+
+    #[cfg(unix)]
+    fn set_rlimits(&self) -> std::io::Result<()> {
+        let limit = |value: u64| libc::rlimit { rlim_cur: value as libc::rlim_t, rlim_max: value as libc::rlim_t };
+        // SAFETY: setrlimit only reads the struct passed to it
+        unsafe {
+            if let Some(mb) = self.max_memory_mb {
+                if libc::setrlimit(libc::RLIMIT_AS, &limit(mb.saturating_mul(1024 * 1024))) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            if let Some(files) = self.max_open_files {
+                if libc::setrlimit(libc::RLIMIT_NOFILE, &limit(files)) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProcessGroup {
+    /// Set up `command` so its child can be attached to a group. Call this
+    /// before spawning.
+    pub fn prepare(command: &mut Command) {
+        #[cfg(unix)]
+        command.process_group(0);
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+
+    /// Group a child that was spawned from a `prepare`d command. On Unix
+    /// `limits` were already applied by `ProcessLimits::apply`.
+    #[cfg(unix)]
+    pub fn attach(child: &Child, _limits: &ProcessLimits) -> Result<ProcessGroup, AppError> {
+        let pid = child.id().ok_or("Process has already exited")?;
+        // process_group(0) makes the child's pid its group id
+        Ok(ProcessGroup { pgid: pid as libc::pid_t })
+    }
+
+    #[cfg(windows)]
+    pub fn attach(child: &Child, limits: &ProcessLimits) -> Result<ProcessGroup, AppError> {
+        use windows_sys::Win32::Foundation::HANDLE;
+        use windows_sys::Win32::System::JobObjects::{
+            AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+            SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+            JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+        };
+
+        let process = child.raw_handle().ok_or("Process has already exited")? as HANDLE;
+
+        // SAFETY: plain Win32 calls on handles we own; `info` outlives the
+        // call that reads it and its size is passed alongside it
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job.is_null() {
+                return Err(format!("Failed to create job object: {}", std::io::Error::last_os_error()).into());
+            }
+            // Owned from here on, so the early returns below close it
+            let job = JobHandle(job);
+
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            if let Some(mb) = limits.max_memory_mb {
+                info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+                info.ProcessMemoryLimit = mb.saturating_mul(1024 * 1024) as usize;
+            }
+            if SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            ) == 0
+            {
+                return Err(format!("Failed to configure job object: {}", std::io::Error::last_os_error()).into());
+            }
+
+            if AssignProcessToJobObject(job.0, process) == 0 {
+                return Err(format!("Failed to assign process to job object: {}", std::io::Error::last_os_error()).into());
+            }
+
+            Ok(ProcessGroup { job })
+        }
+    }
+
+    /// Ask every process in the group to shut down (SIGTERM). Windows has
+    /// no equivalent for windowless processes.
+    #[cfg(unix)]
+    pub fn terminate(&self) {
+        // SAFETY: killpg(2) has no memory-safety requirements; a group that
+        // no longer exists just yields ESRCH
+        unsafe {
+            libc::killpg(self.pgid, libc::SIGTERM);
+        }
+    }
+
+    /// Forcefully kill every process in the group.
+    pub fn kill(&self) {
+        #[cfg(unix)]
+        // SAFETY: as in `terminate`
+        unsafe {
+            libc::killpg(self.pgid, libc::SIGKILL);
+        }
+        #[cfg(windows)]
+        // SAFETY: the job handle is valid until `JobHandle` is dropped
+        unsafe {
+            windows_sys::Win32::System::JobObjects::TerminateJobObject(self.job.0, 1);
+        }
+    }
+}
+
+/// Ask the process `pid` to exit: SIGTERM on Unix, `TerminateProcess` on
+/// Windows.
+pub fn terminate_pid(pid: u32) -> Result<(), AppError> {
+    #[cfg(unix)]
+    {
+        // SAFETY: kill(2) has no memory-safety requirements
+        if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+            return Err(format!("Failed to signal process {}: {}", pid, std::io::Error::last_os_error()).into());
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::Foundation::CloseHandle;
+        use windows_sys::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+        // SAFETY: the handle is checked before use and closed on every path
+        unsafe {
+            let process = OpenProcess(PROCESS_TERMINATE, 0, pid);
+            if process.is_null() {
+                return Err(format!("Failed to open process {}: {}", pid, std::io::Error::last_os_error()).into());
+            }
+            let terminated = TerminateProcess(process, 1);
+            let error = std::io::Error::last_os_error();
+            CloseHandle(process);
+            if terminated == 0 {
+                return Err(format!("Failed to terminate process {}: {}", pid, error).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// PIDs of the processes listening on TCP `port`, from `netstat -ano` on
+/// Windows, `lsof` on macOS and `ss` on other Unixes. Empty when nothing
+/// listens there or the tool isn't available.
+pub async fn listening_pids(port: u16) -> Vec<u32> {
+    #[cfg(windows)]
+    let output = Command::new("netstat").arg("-ano").output().await;
+    #[cfg(target_os = "macos")]
+    let output = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-t"])
+        .output()
+        .await;
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let output = Command::new("ss")
+        .args(["-Hltnp", "sport", "=", &format!(":{}", port)])
+        .output()
+        .await;
+
+    let stdout = match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+        Err(e) => {
+            tracing::warn!("Failed to list listening sockets: {}", e);
+            return Vec::new();
+        }
+    };
+    let mut pids = parse_listening_pids(&stdout, port);
+    // IPv4 and IPv6 sockets of the same process show up separately
+    pids.sort_unstable();
+    pids.dedup();
+    pids
+}
+
+/// `TCP  127.0.0.1:8787  0.0.0.0:0  LISTENING  1234`
+#[cfg(windows)]
+fn parse_listening_pids(output: &str, port: u16) -> Vec<u32> {
+    let suffix = format!(":{}", port);
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            match columns[..] {
+                ["TCP", local, _, "LISTENING", pid] if local.ends_with(&suffix) => pid.parse().ok(),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// One PID per line, already filtered by `lsof`
+#[cfg(target_os = "macos")]
+fn parse_listening_pids(output: &str, _port: u16) -> Vec<u32> {
+    output.lines().filter_map(|line| line.trim().parse().ok()).collect()
+}
+
+/// `LISTEN 0 511 0.0.0.0:8787 0.0.0.0:* users:(("node",pid=1234,fd=20))`,
+/// already filtered by port. `ss` only shows the owner of sockets this
+/// user can see.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn parse_listening_pids(output: &str, _port: u16) -> Vec<u32> {
+    output
+        .split("pid=")
+        .skip(1)
+        .filter_map(|rest| {
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+/// Owned Win32 job object handle, closed on drop.
+#[cfg(windows)]
+struct JobHandle(windows_sys::Win32::Foundation::HANDLE);
+
+// SAFETY: a job object handle may be used and closed from any thread
+#[cfg(windows)]
+unsafe impl Send for JobHandle {}
+#[cfg(windows)]
+unsafe impl Sync for JobHandle {}
+
+#[cfg(windows)]
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        // SAFETY: we own the handle and close it exactly once
+        unsafe {
+            windows_sys::Win32::Foundation::CloseHandle(self.0);
+        }
+    }
+}
@@ -1,63 +1,1936 @@
-use tokio::process::{Command, Child};
-use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{broadcast, Mutex as TokioMutex};
+use std::collections::{HashMap, VecDeque};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::commands::ServerStatus;
+use crate::env;
+use crate::error::AppError;
+use crate::events;
+use crate::log_rotate::LogRotator;
+use crate::notification;
+use crate::paths;
+use crate::process::{ProcessGroup, ProcessLimits};
+use crate::tray::{self, ServiceState};
+use crate::AppState;
+use tauri::{Emitter, Manager};
+
+/// Number of server output lines kept for get_server_logs
+pub const DEFAULT_LOG_CAPACITY: usize = 500;
+/// How long the server gets to exit after SIGTERM before it is killed
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Lines a slow live-log subscriber may fall behind before skipping ahead
+const LOG_CHANNEL_CAPACITY: usize = 256;
+
+/// How far above the requested port `auto_port_fallback` looks
+const PORT_FALLBACK_SPAN: u16 = 100;
+
+/// How often the crash watcher checks whether the child has exited
+const CRASH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// A server that stayed up this long before crashing gets a fresh
+/// restart budget
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+/// Log lines included in the `server-crashed` event
+const CRASH_LOG_LINES: usize = 20;
+
+/// Source of `ServerHandle::id`, used to tell handles apart across restarts
+static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// First file descriptor systemd passes to a socket-activated process
+#[cfg(target_os = "linux")]
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// `engines` from the server's package.json, read once per app session
+static ENGINE_REQUIREMENTS: OnceLock<Option<NodeEngine>> = OnceLock::new();
+
+/// Tauri's resource directory, recorded at setup by `set_resource_dir`
+static RESOURCE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// The app data directory, recorded at setup by `set_app_data_dir`
+static APP_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Name of the PID file release builds write to the app data directory
+const PID_FILE: &str = "server.pid";
+
+
+/// Parameters for starting (and automatically restarting) the server.
+/// Build one with `ServerConfig::builder()` or start from `Default`.
+#[derive(Clone)]
+pub struct ServerConfig {
+    pub port: u16,
+    /// Fall back to a nearby free port when `port` is taken
+    pub auto_port_fallback: bool,
+    /// Extra environment variables for the Node.js process. These win over
+    /// a `.env` file in the server directory.
+    pub env: HashMap<String, String>,
+    /// Appended after the server script (or `npm run dev --`)
+    pub extra_args: Vec<String>,
+    /// Overrides the directory the server is launched from
+    pub working_dir: Option<PathBuf>,
+    /// How long a freshly started server gets to become healthy
+    pub startup_timeout: Duration,
+    /// Lines of output kept for get_server_logs
+    pub log_capacity: usize,
+    /// Use this Node.js binary instead of searching for one
+    pub node_binary: Option<PathBuf>,
+    /// Run this script with Node.js instead of the bundled server (or
+    /// `npm run dev` in development)
+    pub script: Option<PathBuf>,
+    /// Runs the dev script in development; detected from lock files if unset
+    pub package_manager: Option<PackageManager>,
+    /// Memory cap for the server process; see `ProcessLimits`
+    pub max_memory_mb: Option<u64>,
+    /// Open file cap for the server process (Unix only)
+    pub max_open_files: Option<u64>,
+    /// Keep a pipe to the server's stdin for `send_server_stdin`
+    pub capture_stdin: bool,
+    /// Text on stdout that means the server is ready. Without one, startup
+    /// waits for `SERVER_READY_BANNER` or a passing health check.
+    pub ready_sentinel: Option<String>,
+    /// Where to write the server's PID. Release builds default to
+    /// `app_data_dir()/server.pid`; debug builds write none unless asked.
+    pub pid_file: Option<PathBuf>,
+    /// What counts as a passing health check, both during startup and
+    /// while polling
+    pub health_check: HealthCheck,
+    /// How often a running server's health endpoint is checked; `None`
+    /// turns the checks off. Servers without one should disable them.
+    pub health_poll_interval: Option<Duration>,
+    /// Failed health checks in a row after which the server is restarted
+    pub health_failure_threshold: u8,
+    /// Where `get_server_metrics` reads from; `/metrics` if unset
+    pub metrics_path: Option<String>,
+    /// Restart attempts after a crash before giving up
+    pub max_restarts: u32,
+    /// Crashes in a row, each within `circuit_reset_interval` of starting,
+    /// after which automatic restarts stop (the circuit opens)
+    pub circuit_open_threshold: u32,
+    /// A server that stays up this long resets the fast-crash count
+    pub circuit_reset_interval: Duration,
+    /// How long an open circuit waits before allowing one more restart
+    pub circuit_half_open_delay: Duration,
+    /// Delay before the first restart attempt; doubles on each further try
+    pub restart_backoff: Duration,
+    pub max_restart_backoff: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            port: crate::window::DEFAULT_SERVER_PORT,
+            auto_port_fallback: true,
+            env: HashMap::new(),
+            extra_args: Vec::new(),
+            working_dir: None,
+            startup_timeout: Duration::from_secs(30),
+            log_capacity: DEFAULT_LOG_CAPACITY,
+            node_binary: None,
+            script: None,
+            package_manager: None,
+            pid_file: None,
+            ready_sentinel: None,
+            capture_stdin: false,
+            max_memory_mb: None,
+            max_open_files: None,
+            health_check: HealthCheck::default(),
+            health_poll_interval: Some(Duration::from_secs(30)),
+            health_failure_threshold: 3,
+            metrics_path: None,
+            max_restarts: 3,
+            circuit_open_threshold: 5,
+            circuit_reset_interval: Duration::from_secs(60),
+            circuit_half_open_delay: Duration::from_secs(5 * 60),
+            restart_backoff: Duration::from_secs(1),
+            max_restart_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn builder() -> ServerConfigBuilder {
+        ServerConfigBuilder::default()
+    }
+
+    fn limits(&self) -> ProcessLimits {
+        ProcessLimits { max_memory_mb: self.max_memory_mb, max_open_files: self.max_open_files }
+    }
+}
+
+/// Chainable construction of a `ServerConfig`; unset fields keep their
+/// defaults.
+#[derive(Default)]
+pub struct ServerConfigBuilder {
+    config: ServerConfig,
+}
+
+impl ServerConfigBuilder {
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.port = port;
+        self
+    }
+
+    pub fn auto_port_fallback(mut self, enabled: bool) -> Self {
+        self.config.auto_port_fallback = enabled;
+        self
+    }
+
+    /// Adds to (rather than replaces) any variables already set.
+    pub fn env(mut self, env: HashMap<String, String>) -> Self {
+        self.config.env.extend(env);
+        self
+    }
+
+    pub fn extra_args(mut self, args: Vec<String>) -> Self {
+        self.config.extra_args = args;
+        self
+    }
+
+    pub fn working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.working_dir = Some(dir.into());
+        self
+    }
+
+    pub fn startup_timeout(mut self, timeout: Duration) -> Self {
+        self.config.startup_timeout = timeout;
+        self
+    }
+
+    pub fn log_capacity(mut self, capacity: usize) -> Self {
+        self.config.log_capacity = capacity;
+        self
+    }
+
+    pub fn node_binary(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.node_binary = Some(path.into());
+        self
+    }
+
+    pub fn script(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.script = Some(path.into());
+        self
+    }
+
+    pub fn max_memory_mb(mut self, mb: u64) -> Self {
+        self.config.max_memory_mb = Some(mb);
+        self
+    }
+
+    pub fn max_open_files(mut self, files: u64) -> Self {
+        self.config.max_open_files = Some(files);
+        self
+    }
+
+    pub fn capture_stdin(mut self, enabled: bool) -> Self {
+        self.config.capture_stdin = enabled;
+        self
+    }
+
+    pub fn ready_sentinel(mut self, sentinel: impl Into<String>) -> Self {
+        self.config.ready_sentinel = Some(sentinel.into());
+        self
+    }
+
+    pub fn pid_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.pid_file = Some(path.into());
+        self
+    }
+
+    pub fn package_manager(mut self, manager: PackageManager) -> Self {
+        self.config.package_manager = Some(manager);
+        self
+    }
+
+    pub fn health_check(mut self, check: HealthCheck) -> Self {
+        self.config.health_check = check;
+        self
+    }
+
+    pub fn health_check_path(mut self, path: impl Into<String>) -> Self {
+        self.config.health_check.path = path.into();
+        self
+    }
+
+    pub fn health_check_method(mut self, method: HealthCheckMethod) -> Self {
+        self.config.health_check.method = method;
+        self
+    }
+
+    pub fn health_check_expected_body(mut self, text: Option<String>) -> Self {
+        self.config.health_check.expected_body_contains = text;
+        self
+    }
+
+    pub fn health_poll_interval(mut self, interval: Option<Duration>) -> Self {
+        self.config.health_poll_interval = interval;
+        self
+    }
+
+    pub fn health_failure_threshold(mut self, failures: u8) -> Self {
+        self.config.health_failure_threshold = failures;
+        self
+    }
+
+    pub fn metrics_path(mut self, path: impl Into<String>) -> Self {
+        self.config.metrics_path = Some(path.into());
+        self
+    }
+
+    pub fn max_restarts(mut self, restarts: u32) -> Self {
+        self.config.max_restarts = restarts;
+        self
+    }
+
+    pub fn circuit_open_threshold(mut self, failures: u32) -> Self {
+        self.config.circuit_open_threshold = failures;
+        self
+    }
+
+    pub fn circuit_reset_interval(mut self, interval: Duration) -> Self {
+        self.config.circuit_reset_interval = interval;
+        self
+    }
+
+    pub fn circuit_half_open_delay(mut self, delay: Duration) -> Self {
+        self.config.circuit_half_open_delay = delay;
+        self
+    }
+
+    pub fn build(self) -> ServerConfig {
+        self.config
+    }
+}
+
+/// Server settings as sent by the frontend. Every field is optional and
+/// falls back to the defaults it is merged onto.
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerConfigRequest {
+    pub port: Option<u16>,
+    pub auto_port_fallback: Option<bool>,
+    pub env: Option<HashMap<String, String>>,
+    pub extra_args: Option<Vec<String>>,
+    pub working_dir: Option<PathBuf>,
+    pub startup_timeout_secs: Option<u64>,
+    pub log_capacity: Option<usize>,
+    pub node_binary: Option<PathBuf>,
+    pub package_manager: Option<PackageManager>,
+    pub pid_file: Option<PathBuf>,
+    pub ready_sentinel: Option<String>,
+    pub capture_stdin: Option<bool>,
+    pub max_memory_mb: Option<u64>,
+    pub max_open_files: Option<u64>,
+    /// 0 turns health polling off
+    pub health_check_path: Option<String>,
+    pub health_check_method: Option<HealthCheckMethod>,
+    /// An empty string drops the body check
+    pub health_check_expected_body: Option<String>,
+    pub health_poll_interval_secs: Option<u64>,
+    pub health_failure_threshold: Option<u8>,
+    pub metrics_path: Option<String>,
+    pub circuit_open_threshold: Option<u32>,
+    pub circuit_reset_interval_secs: Option<u64>,
+    pub circuit_half_open_delay_secs: Option<u64>,
+}
+
+impl ServerConfigRequest {
+    /// Overlay the fields that were set onto `builder`'s values.
+    pub fn merge_into(self, mut builder: ServerConfigBuilder) -> ServerConfig {
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let Some(enabled) = self.auto_port_fallback {
+            builder = builder.auto_port_fallback(enabled);
+        }
+        if let Some(env) = self.env {
+            builder = builder.env(env);
+        }
+        if let Some(args) = self.extra_args {
+            builder = builder.extra_args(args);
+        }
+        if let Some(dir) = self.working_dir {
+            builder = builder.working_dir(dir);
+        }
+        if let Some(secs) = self.startup_timeout_secs {
+            builder = builder.startup_timeout(Duration::from_secs(secs));
+        }
+        if let Some(capacity) = self.log_capacity {
+            builder = builder.log_capacity(capacity);
+        }
+        if let Some(node) = self.node_binary {
+            builder = builder.node_binary(node);
+        }
+        if let Some(manager) = self.package_manager {
+            builder = builder.package_manager(manager);
+        }
+        if let Some(path) = self.pid_file {
+            builder = builder.pid_file(path);
+        }
+        if let Some(sentinel) = self.ready_sentinel {
+            builder = builder.ready_sentinel(sentinel);
+        }
+        if let Some(enabled) = self.capture_stdin {
+            builder = builder.capture_stdin(enabled);
+        }
+        if let Some(mb) = self.max_memory_mb {
+            builder = builder.max_memory_mb(mb);
+        }
+        if let Some(files) = self.max_open_files {
+            builder = builder.max_open_files(files);
+        }
+        if let Some(path) = self.health_check_path {
+            builder = builder.health_check_path(path);
+        }
+        if let Some(method) = self.health_check_method {
+            builder = builder.health_check_method(method);
+        }
+        if let Some(text) = self.health_check_expected_body {
+            builder = builder.health_check_expected_body((!text.is_empty()).then_some(text));
+        }
+        if let Some(secs) = self.health_poll_interval_secs {
+            builder = builder.health_poll_interval((secs > 0).then(|| Duration::from_secs(secs)));
+        }
+        if let Some(failures) = self.health_failure_threshold {
+            builder = builder.health_failure_threshold(failures);
+        }
+        if let Some(path) = self.metrics_path {
+            builder = builder.metrics_path(path);
+        }
+        if let Some(failures) = self.circuit_open_threshold {
+            builder = builder.circuit_open_threshold(failures);
+        }
+        if let Some(secs) = self.circuit_reset_interval_secs {
+            builder = builder.circuit_reset_interval(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.circuit_half_open_delay_secs {
+            builder = builder.circuit_half_open_delay(Duration::from_secs(secs));
+        }
+        builder.build()
+    }
+}
+
+/// Startup timeouts above this are allowed but probably a mistake
+const MAX_SENSIBLE_STARTUP_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Worth a look, but the server can still start
+    Warning,
+    /// `start` would fail (or misbehave) with this config
+    Error,
+}
+
+/// One problem found by `validate_config`. `field` names the
+/// `ServerConfigRequest` field at fault.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ValidationWarning {
+    pub field: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ValidationWarning {
+    fn new(field: &str, severity: Severity, message: impl Into<String>) -> Self {
+        ValidationWarning { field: field.to_string(), severity, message: message.into() }
+    }
+}
+
+/// Check `config` for mistakes without starting anything: the files it
+/// points at, the port, the environment and the startup timeout.
+pub fn validate_config(config: &ServerConfig) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+
+    // 0 asks the OS for a free port
+    if config.port != 0 && config.port < 1024 {
+        warnings.push(ValidationWarning::new(
+            "port",
+            Severity::Error,
+            format!("Port {} is reserved; use one between 1024 and 65535", config.port),
+        ));
+    }
+
+    if let Some(node) = &config.node_binary {
+        if !node.exists() {
+            warnings.push(ValidationWarning::new(
+                "nodeBinary",
+                Severity::Error,
+                format!("{} does not exist", node.display()),
+            ));
+        } else if !is_executable(node) {
+            warnings.push(ValidationWarning::new(
+                "nodeBinary",
+                Severity::Error,
+                format!("{} is not executable", node.display()),
+            ));
+        }
+    }
+
+    if let Some(dir) = &config.working_dir {
+        if !dir.is_dir() {
+            warnings.push(ValidationWarning::new(
+                "workingDir",
+                Severity::Error,
+                format!("{} is not a directory", dir.display()),
+            ));
+        }
+    }
+
+    if config.script.is_none() && is_development_mode() {
+        let server_dir = config.working_dir.clone().or_else(|| {
+            paths::find_project_root().ok().map(|root| root.join("apps").join("server"))
+        });
+        match server_dir {
+            Some(dir) if dir.join("package.json").is_file() => {}
+            Some(dir) => warnings.push(ValidationWarning::new(
+                "workingDir",
+                Severity::Error,
+                format!("No package.json in {} to run the dev server from", dir.display()),
+            )),
+            None => warnings.push(ValidationWarning::new(
+                "workingDir",
+                Severity::Error,
+                "Could not find the project root to run the dev server from",
+            )),
+        }
+    } else {
+        let script = config.script.clone().unwrap_or_else(get_server_path);
+        if !script.is_file() {
+            warnings.push(ValidationWarning::new(
+                "script",
+                Severity::Error,
+                format!("Server script not found at {}", script.display()),
+            ));
+        }
+    }
+
+    let mut bad_keys: Vec<&String> = config
+        .env
+        .keys()
+        .filter(|key| key.is_empty() || key.contains('=') || key.contains('\0'))
+        .collect();
+    bad_keys.sort();
+    for key in bad_keys {
+        warnings.push(ValidationWarning::new(
+            "env",
+            Severity::Error,
+            format!("'{}' is not a valid environment variable name", key),
+        ));
+    }
+
+    if config.startup_timeout.is_zero() {
+        warnings.push(ValidationWarning::new(
+            "startupTimeoutSecs",
+            Severity::Error,
+            "Startup timeout must be greater than zero",
+        ));
+    } else if config.startup_timeout > MAX_SENSIBLE_STARTUP_TIMEOUT {
+        warnings.push(ValidationWarning::new(
+            "startupTimeoutSecs",
+            Severity::Warning,
+            format!(
+                "A {}s startup timeout hides a hung server for a long time",
+                config.startup_timeout.as_secs()
+            ),
+        ));
+    }
+
+    if config.health_poll_interval.is_some() && config.health_failure_threshold == 0 {
+        warnings.push(ValidationWarning::new(
+            "healthFailureThreshold",
+            Severity::Warning,
+            "A threshold of 0 is treated as 1: the first failed health check restarts the server",
+        ));
+    }
+    if config.health_poll_interval.is_some() && config.ready_sentinel.is_some() {
+        warnings.push(ValidationWarning::new(
+            "healthPollIntervalSecs",
+            Severity::Warning,
+            "Health polling expects a health endpoint; turn it off if the server has none",
+        ));
+    }
+    if config.health_check.method == HealthCheckMethod::Head && config.health_check.expected_body_contains.is_some() {
+        warnings.push(ValidationWarning::new(
+            "healthCheckExpectedBody",
+            Severity::Error,
+            "HEAD responses have no body; use GET to check the body",
+        ));
+    }
+
+    warnings
+}
+
+/// What the health poller has seen of a running server.
+#[derive(Default)]
+struct Watchdog {
+    /// Failed health checks in a row
+    failures: AtomicU32,
+    last_success: Mutex<Option<Instant>>,
+    /// Checks are skipped until then, set by `disable_watchdog_temporarily`
+    paused_until: Mutex<Option<Instant>>,
+}
+
+impl Watchdog {
+    fn paused(&self) -> bool {
+        let paused_until = self.paused_until.lock().unwrap_or_else(|e| e.into_inner());
+        paused_until.is_some_and(|until| Instant::now() < until)
+    }
+}
 
 pub struct ServerHandle {
-    child: Child,
+    // Shared with the crash watcher, which polls it with try_wait
+    child: Arc<TokioMutex<Child>>,
+    id: u64,
+    pid: Option<u32>,
     pub port: u16,
+    config: ServerConfig,
+    started_at: Instant,
+    /// Set by `stop` so the crash watcher ignores the resulting exit
+    stopping: Arc<AtomicBool>,
+    /// Set by the crash watcher once it has seen the process exit
+    exited: Arc<AtomicBool>,
+    /// Shared with the health poller
+    watchdog: Arc<Watchdog>,
+    /// How the process ended, once `is_alive` has seen it exit
+    pub exit_status: Option<ExitStatus>,
+    /// The output line that showed the server was ready, if it printed one
+    pub ready_line: Option<String>,
+    /// Set when started with `capture_stdin`. Locked for a whole message so
+    /// concurrent writes don't interleave.
+    stdin: Option<TokioMutex<ChildStdin>>,
+    log_tx: broadcast::Sender<LogLine>,
+    log_subscribed: AtomicBool,
+    /// The child and its workers; `None` if grouping failed
+    group: Option<Arc<ProcessGroup>>,
+    /// Written once the child has spawned, removed when it exits
+    pid_file: Option<PathBuf>,
+    shutdown_timeout: Duration,
+}
+
+/// One line of server output.
+#[derive(Clone, serde::Serialize)]
+pub struct LogLine {
+    /// Unix time in milliseconds when the line was read
+    pub timestamp_ms: u64,
+    /// `debug`, `info`, `warn` or `error`
+    pub level: String,
+    pub message: String,
+    #[serde(skip)]
+    from_stderr: bool,
+}
+
+impl LogLine {
+    /// Lines on stderr are errors and lines on stdout info, unless the line
+    /// starts with a level of its own (`[warn] ...`, `WARN: ...`).
+    fn new(message: String, from_stderr: bool) -> Self {
+        let first_word = message
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .trim_matches(|c: char| !c.is_ascii_alphabetic())
+            .to_ascii_lowercase();
+        let level = match first_word.as_str() {
+            "debug" | "trace" => "debug",
+            "info" => "info",
+            "warn" | "warning" => "warn",
+            "error" | "fatal" => "error",
+            _ if from_stderr => "error",
+            _ => "info",
+        };
+        LogLine {
+            timestamp_ms: chrono::Utc::now().timestamp_millis() as u64,
+            level: level.to_string(),
+            message,
+            from_stderr,
+        }
+    }
+
+    /// `2024-01-01T12:00:00.000Z [info] message`
+    pub fn to_text(&self) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(self.timestamp_ms as i64)
+            .unwrap_or_default()
+            .format("%Y-%m-%dT%H:%M:%S%.3fZ");
+        format!("{} [{}] {}", time, self.level, self.message)
+    }
+}
+
+/// How `export_logs` writes each line.
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON object per line
+    Json,
+    #[default]
+    Text,
+}
+
+/// A restart of the server or reconnect of the tunnel, as returned by
+/// `get_server_history`.
+#[derive(Clone, serde::Serialize)]
+pub struct RestartEvent {
+    /// ISO-8601, UTC
+    pub timestamp: String,
+    pub reason: String,
+}
+
+/// Restarts during this app session. Lives in app state like `LogBuffer`,
+/// so it accumulates across the handles each restart replaces.
+#[derive(Clone, Default)]
+pub struct RestartHistory {
+    events: Arc<Mutex<Vec<RestartEvent>>>,
+}
+
+impl RestartHistory {
+    pub fn record(&self, reason: impl Into<String>) {
+        let event = RestartEvent {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            reason: reason.into(),
+        };
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).push(event);
+    }
+
+    /// Every restart so far, oldest first.
+    pub fn events(&self) -> Vec<RestartEvent> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn count(&self) -> u32 {
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).len() as u32
+    }
+
+    pub fn last_reason(&self) -> Option<String> {
+        let events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events.last().map(|event| event.reason.clone())
+    }
+}
+
+/// Whether a crashed server is still restarted automatically.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CircuitState {
+    /// Crashes are restarted as usual
+    #[default]
+    Closed,
+    /// Too many fast crashes in a row; nothing is restarted until the
+    /// half-open delay passes or `reset_circuit_breaker` is called
+    Open,
+    /// One restart is being tried; a fast crash opens the circuit again
+    HalfOpen,
+}
+
+/// Counts crashes that come soon after a start and stops automatic
+/// restarts once `circuit_open_threshold` of them happen in a row. Lives in
+/// app state like `RestartHistory`, so the count survives restarts.
+#[derive(Clone, Default)]
+pub struct CircuitBreaker {
+    inner: Arc<Mutex<CircuitInner>>,
+}
+
+#[derive(Default)]
+struct CircuitInner {
+    state: CircuitState,
+    consecutive_fast_failures: u32,
+    /// Bumped whenever the circuit opens or is reset, so a half-open timer
+    /// from an earlier opening does nothing
+    generation: u64,
+}
+
+impl CircuitBreaker {
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Close the circuit and forget the fast crashes so far.
+    pub fn reset(&self) {
+        let mut inner = self.lock();
+        inner.state = CircuitState::Closed;
+        inner.consecutive_fast_failures = 0;
+        inner.generation += 1;
+    }
+
+    /// Count a failure of a server that ran for `uptime`. Returns the
+    /// generation of the opening if this opened the circuit (or it already
+    /// was), together with the fast-crash count.
+    fn record_failure(&self, uptime: Duration, config: &ServerConfig) -> Option<(u64, u32)> {
+        let mut inner = self.lock();
+        if uptime >= config.circuit_reset_interval {
+            inner.consecutive_fast_failures = 0;
+            if inner.state == CircuitState::HalfOpen {
+                inner.state = CircuitState::Closed;
+            }
+        } else {
+            inner.consecutive_fast_failures += 1;
+        }
+        let open = match inner.state {
+            CircuitState::Open => true,
+            CircuitState::HalfOpen => inner.consecutive_fast_failures > 0,
+            CircuitState::Closed => inner.consecutive_fast_failures >= config.circuit_open_threshold.max(1),
+        };
+        if !open {
+            return None;
+        }
+        if inner.state != CircuitState::Open {
+            inner.state = CircuitState::Open;
+            inner.generation += 1;
+        }
+        Some((inner.generation, inner.consecutive_fast_failures))
+    }
+
+    /// Move an open circuit to half-open, unless it was reset or reopened
+    /// since `generation`.
+    fn half_open(&self, generation: u64) -> bool {
+        let mut inner = self.lock();
+        let current = inner.state == CircuitState::Open && inner.generation == generation;
+        if current {
+            inner.state = CircuitState::HalfOpen;
+        }
+        current
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CircuitInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Filters for `LogBuffer::query`; unset fields match everything.
+#[derive(Default)]
+pub struct LogQuery {
+    /// Only lines at or after this Unix time in milliseconds
+    pub since_ms: Option<u64>,
+    pub level: Option<String>,
+    /// Case-insensitive substring of the message
+    pub search: Option<String>,
+    /// Keep only the newest this many matches
+    pub limit: Option<usize>,
+}
+
+/// Most recent server output lines. Lives in app state rather than the
+/// handle so logs stay readable after the server stops.
+#[derive(Clone)]
+pub struct LogBuffer {
+    lines: Arc<Mutex<VecDeque<LogLine>>>,
+    capacity: Arc<AtomicUsize>,
+    /// Every line is also appended here, if set
+    file: Arc<OnceLock<TokioMutex<LogRotator>>>,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        LogBuffer {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity: Arc::new(AtomicUsize::new(capacity)),
+            file: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Also write every line to `rotator`'s file from now on. Only the
+    /// first call has any effect.
+    pub(crate) fn attach_file(&self, rotator: LogRotator) {
+        let _ = self.file.set(TokioMutex::new(rotator));
+    }
+
+    pub(crate) fn file(&self) -> Option<&TokioMutex<LogRotator>> {
+        self.file.get()
+    }
+
+    /// Change how many lines are kept, dropping the oldest if shrinking.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Relaxed);
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        while lines.len() > capacity {
+            lines.pop_front();
+        }
+    }
+
+    fn push(&self, line: LogLine) {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        while !lines.is_empty() && lines.len() >= capacity {
+            lines.pop_front();
+        }
+        if capacity > 0 {
+            lines.push_back(line);
+        }
+    }
+
+    /// Drop every buffered line and empty the log file, if there is one.
+    pub async fn clear(&self) -> Result<(), AppError> {
+        self.lines.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        if let Some(file) = self.file() {
+            file.lock()
+                .await
+                .truncate()
+                .await
+                .map_err(|e| format!("Failed to clear the server log file: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// The last `limit` lines, oldest first.
+    pub fn recent(&self, limit: usize) -> Vec<LogLine> {
+        self.query(&LogQuery { limit: Some(limit), ..Default::default() })
+    }
+
+    /// Lines matching every filter in `query`, oldest first.
+    pub fn query(&self, query: &LogQuery) -> Vec<LogLine> {
+        let search = query.search.as_ref().map(|s| s.to_lowercase());
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        let mut matches: Vec<LogLine> = lines
+            .iter()
+            .filter(|line| query.since_ms.is_none_or(|since| line.timestamp_ms >= since))
+            .filter(|line| query.level.as_ref().is_none_or(|level| line.level.eq_ignore_ascii_case(level)))
+            .filter(|line| search.as_ref().is_none_or(|s| line.message.to_lowercase().contains(s)))
+            .cloned()
+            .collect();
+        if let Some(limit) = query.limit {
+            let skip = matches.len().saturating_sub(limit);
+            matches.drain(..skip);
+        }
+        matches
+    }
+
+    /// Write every buffered line to `path`, replacing the file. Returns the
+    /// number of lines written.
+    pub fn export(&self, path: &Path, format: ExportFormat) -> Result<u64, AppError> {
+        let lines = self.query(&LogQuery::default());
+        let mut out = String::new();
+        for line in &lines {
+            match format {
+                ExportFormat::Json => out.push_str(
+                    &serde_json::to_string(line).map_err(|e| format!("Failed to encode log line: {}", e))?,
+                ),
+                ExportFormat::Text => out.push_str(&line.to_text()),
+            }
+            out.push('\n');
+        }
+        std::fs::write(path, out)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(lines.len() as u64)
+    }
+}
+
+/// Remember where Tauri put the bundled resources. Called once from setup.
+pub fn set_resource_dir(dir: PathBuf) {
+    let _ = RESOURCE_DIR.set(dir);
+}
+
+/// Remember the app data directory for the default PID file. Called once
+/// from setup.
+pub fn set_app_data_dir(dir: PathBuf) {
+    let _ = APP_DATA_DIR.set(dir);
+}
+
+/// Release builds keep a PID file in the app data directory for external
+/// tooling; debug builds don't, so several checkouts can run at once.
+fn default_pid_file() -> Option<PathBuf> {
+    if cfg!(debug_assertions) {
+        return None;
+    }
+    APP_DATA_DIR.get().map(|dir| dir.join(PID_FILE))
+}
+
+/// Write `pid` and a newline to `path` via a temporary file and a rename,
+/// so readers never see it half written.
+fn write_pid_file(path: &Path, pid: u32) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, format!("{}\n", pid))?;
+    std::fs::rename(&tmp, path)
+}
+
+fn remove_pid_file(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!("Failed to remove PID file {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// The app's resource directory, or the executable's directory if setup
+/// couldn't resolve it. tauri.conf.json bundles `resources/*`, so bundled
+/// files live under `resource_dir()/resources`.
+pub fn resource_dir() -> PathBuf {
+    RESOURCE_DIR.get().cloned().unwrap_or_else(|| {
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+            .unwrap_or_default()
+    })
+}
+
+// Path to the bundled Node.js server executable
+fn get_server_path() -> PathBuf {
+    let bundled = resource_dir().join("resources").join("server").join("index.js");
+    #[cfg(debug_assertions)]
+    if !bundled.exists() {
+        if let Some(path) = find_dev_server_path() {
+            return path;
+        }
+    }
+    bundled
+}
+
+/// `src-tauri/resources/server/index.js` in a checkout, searching up from
+/// the executable (`target/debug` or `target/release`).
+#[cfg(debug_assertions)]
+fn find_dev_server_path() -> Option<PathBuf> {
+    let exe_path = std::env::current_exe().ok()?;
+    exe_path
+        .ancestors()
+        .skip(1)
+        .take(paths::DEV_SEARCH_DEPTH)
+        .flat_map(|dir| [dir.join("src-tauri"), dir.to_path_buf()])
+        .map(|dir| dir.join("resources").join("server").join("index.js"))
+        .find(|path| path.exists())
+}
+
+impl ServerHandle {
+    /// Wrap a freshly spawned child, streaming its stdout/stderr into
+    /// `logs` and the live log channel.
+    /// Also returns a receiver that sees every line from the first one on,
+    /// for `wait_for_ready`.
+    fn new(
+        mut child: Child,
+        port: u16,
+        config: ServerConfig,
+        logs: LogBuffer,
+    ) -> (Self, broadcast::Receiver<LogLine>) {
+        let (log_tx, output) = broadcast::channel(LOG_CHANNEL_CAPACITY);
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(pipe_output(stdout, false, logs.clone(), log_tx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(pipe_output(stderr, true, logs, log_tx.clone()));
+        }
+
+        let group = ProcessGroup::attach(&child, &config.limits())
+            .inspect_err(|e| tracing::warn!("Server workers won't be stopped with it: {}", e))
+            .ok()
+            .map(Arc::new);
+
+        let stdin = child.stdin.take().map(TokioMutex::new);
+        let handle = ServerHandle {
+            id: NEXT_HANDLE_ID.fetch_add(1, Ordering::Relaxed),
+            pid: child.id(),
+            group,
+            child: Arc::new(TokioMutex::new(child)),
+            port,
+            config,
+            started_at: Instant::now(),
+            stopping: Arc::new(AtomicBool::new(false)),
+            exited: Arc::new(AtomicBool::new(false)),
+            watchdog: Arc::default(),
+            exit_status: None,
+            ready_line: None,
+            stdin,
+            pid_file: None,
+            log_tx,
+            log_subscribed: AtomicBool::new(false),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        };
+        (handle, output)
+    }
+
+    /// The config this server was started with, reused for restarts.
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    /// Time since this process was spawned.
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Health checks failed in a row so far; 0 without health polling.
+    pub fn watchdog_failures(&self) -> u32 {
+        self.watchdog.failures.load(Ordering::Relaxed)
+    }
+
+    /// When the health poller last saw the server healthy.
+    pub fn last_watchdog_success(&self) -> Option<Instant> {
+        *self.watchdog.last_success.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Skip health checks for `duration`, for a server that is known to be
+    /// busy (a slow migration, say) and shouldn't be restarted for it.
+    pub fn pause_watchdog(&self, duration: Duration) {
+        *self.watchdog.paused_until.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now() + duration);
+        self.watchdog.failures.store(0, Ordering::Relaxed);
+    }
+
+    /// OS process ID of the spawned child, until it is known to have
+    /// exited. Still set while `stop` is waiting for it to go.
+    pub fn pid(&self) -> Option<u32> {
+        if self.exited.load(Ordering::SeqCst) {
+            None
+        } else {
+            self.pid
+        }
+    }
+
+    /// Write `message` and a newline to the server's stdin.
+    pub async fn write_stdin(&self, message: &str) -> Result<(), AppError> {
+        let stdin = self.stdin.as_ref().ok_or(AppError::StdinNotCaptured)?;
+        let mut stdin = stdin.lock().await;
+        let line = format!("{}\n", message);
+        stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to server stdin: {}", e))?;
+        stdin
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to write to server stdin: {}", e))?;
+        Ok(())
+    }
+
+    /// Take the server's stdin pipe for direct use; later `write_stdin`
+    /// calls then fail with `StdinNotCaptured`.
+    pub fn take_stdin(&mut self) -> Option<ChildStdin> {
+        self.stdin.take().map(TokioMutex::into_inner)
+    }
+
+    /// A receiver for live output lines. Only the first call gets one, so
+    /// repeated subscriptions from the frontend don't duplicate events.
+    pub fn subscribe_logs(&self) -> Option<broadcast::Receiver<LogLine>> {
+        if self.log_subscribed.swap(true, Ordering::SeqCst) {
+            None
+        } else {
+            Some(self.log_tx.subscribe())
+        }
+    }
+}
+
+async fn pipe_output<R: AsyncRead + Unpin>(
+    stream: R,
+    from_stderr: bool,
+    logs: LogBuffer,
+    log_tx: broadcast::Sender<LogLine>,
+) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        tracing::info!(target: "server", "{}", line);
+        let line = LogLine::new(line, from_stderr);
+        logs.push(line.clone());
+        if let Some(file) = logs.file() {
+            let mut file = file.lock().await;
+            let text = format!("{}\n", line.to_text());
+            // The in-memory buffer still has the line if the disk is full
+            let _ = file.write_all(text.as_bytes()).await;
+            let _ = file.flush().await;
+        }
+        // No receivers is fine; the buffer above still has the line
+        let _ = log_tx.send(line);
+    }
+}
+
+pub async fn start(config: ServerConfig, logs: LogBuffer) -> Result<ServerHandle, AppError> {
+    // Port 0 means "let the OS pick"; resolve it up front so the handle
+    // reports the port Node.js actually listens on
+    let (port, listener) = if config.port == 0 {
+        allocate_port()?
+    } else if config.auto_port_fallback {
+        let range = config.port..=config.port.saturating_add(PORT_FALLBACK_SPAN);
+        let port = find_available_port(config.port, range)?;
+        if port != config.port {
+            tracing::info!("Port {} is in use, starting server on {}", config.port, port);
+        }
+        (port, None)
+    } else {
+        (config.port, None)
+    };
+    logs.set_capacity(config.log_capacity);
+
+    // Check if we're in development mode
+    let child = if config.script.is_none() && is_development_mode() {
+        start_dev_server(port, listener.as_ref(), &config).await?
+    } else {
+        start_production_server(port, listener.as_ref(), &config).await?
+    };
+    // The child has its own copy of the socket now
+    drop(listener);
+
+    let startup_timeout = config.startup_timeout;
+    let pid_file = config.pid_file.clone().or_else(default_pid_file);
+    let (mut handle, output) = ServerHandle::new(child, port, config, logs);
+    if let (Some(path), Some(pid)) = (pid_file, handle.pid) {
+        match write_pid_file(&path, pid) {
+            Ok(()) => handle.pid_file = Some(path),
+            Err(e) => tracing::warn!("Failed to write PID file {}: {}", path.display(), e),
+        }
+    }
+
+    let mut stderr = Vec::new();
+    let ready = tokio::time::timeout(startup_timeout, wait_for_ready(&handle, output, &mut stderr))
+        .await
+        .unwrap_or_else(|_| {
+            Err(AppError::Timeout {
+                operation: "Server startup".to_string(),
+                after: startup_timeout,
+            })
+        });
+    match ready {
+        Ok(line) => handle.ready_line = line,
+        Err(e) => {
+            let _ = stop(handle).await;
+            // Whatever the server wrote to stderr is usually the real reason
+            // it didn't come up
+            if stderr.is_empty() {
+                return Err(e);
+            }
+            return Err(AppError::Other(format!("{}. Server stderr:\n{}", e, stderr.join("\n"))));
+        }
+    }
+
+    Ok(handle)
+}
+
+/// Whether the server process is still running, checked without waiting on
+/// it. The first time it is found to have exited, its status is kept in
+/// `exit_status`.
+pub async fn is_alive(handle: &mut ServerHandle) -> bool {
+    if handle.exit_status.is_some() {
+        return false;
+    }
+    match handle.child.lock().await.try_wait() {
+        Ok(Some(status)) => {
+            handle.exit_status = Some(status);
+            false
+        }
+        Ok(None) => true,
+        // Can't tell; trust the crash watcher
+        Err(_) => !handle.exited.load(Ordering::SeqCst),
+    }
+}
+
+/// Printed by the server once it is accepting connections
+pub const SERVER_READY_BANNER: &str = "server listening on";
+
+/// How often `wait_for_ready` checks on the process and its health endpoint
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Resolve once the server is ready, or fail if it exits first. With a
+/// `ready_sentinel` configured that means printing a line containing it on
+/// stdout; otherwise printing `SERVER_READY_BANNER` or answering its health
+/// check. Returns the line that marked it ready, if any. Lines written to
+/// stderr are collected into `stderr` along the way.
+async fn wait_for_ready(
+    handle: &ServerHandle,
+    mut output: broadcast::Receiver<LogLine>,
+    stderr: &mut Vec<String>,
+) -> Result<Option<String>, AppError> {
+    let sentinel = handle.config.ready_sentinel.as_deref();
+    loop {
+        tokio::select! {
+            line = output.recv() => {
+                // Lagging only drops lines; the handle keeps a sender alive,
+                // so the channel can't close under us
+                let Ok(line) = line else { continue };
+                if line.from_stderr {
+                    stderr.push(line.message.clone());
+                }
+                let ready = match sentinel {
+                    Some(sentinel) => !line.from_stderr && line.message.contains(sentinel),
+                    None => line.message.contains(SERVER_READY_BANNER),
+                };
+                if ready {
+                    return Ok(Some(line.message));
+                }
+            }
+            _ = tokio::time::sleep(READY_POLL_INTERVAL) => {
+                let exited = handle.child.lock().await.try_wait()?;
+                if let Some(status) = exited {
+                    // Give the readers a moment to pass on the last lines,
+                    // which usually say what went wrong
+                    while let Ok(Ok(line)) = tokio::time::timeout(READY_POLL_INTERVAL, output.recv()).await {
+                        if line.from_stderr {
+                            stderr.push(line.message);
+                        }
+                    }
+                    return Err(format!("Server exited during startup ({})", status).into());
+                }
+                // A sentinel stands in for the health endpoint, which may
+                // not be up until later
+                if sentinel.is_none() && probe_server_health(handle.port, &handle.config.health_check, 1, Duration::ZERO).await.is_ok() {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+/// Whether the last output before a crash is Node.js (or V8) failing to
+/// allocate: what hitting `max_memory_mb` looks like from outside.
+fn ran_out_of_memory(last_logs: &[LogLine]) -> bool {
+    last_logs.iter().any(|line| {
+        let message = line.message.to_lowercase();
+        message.contains("out of memory") || message.contains("allocation failed")
+    })
+}
+
+/// Watch the server process in the background and restart it if it exits
+/// unexpectedly, replacing the handle in `AppState` with the new one.
+///
+/// Emits `server-crashed` on every crash and `server-failed` once
+/// `max_restarts` attempts in a row have failed, at which point the server
+/// state is cleared. Also starts the health poller when the handle's config
+/// asks for one.
+pub fn spawn_crash_watcher(app: tauri::AppHandle, handle: &ServerHandle) {
+    spawn_crash_watcher_with_count(app, handle, 0);
+}
+
+fn spawn_crash_watcher_with_count(app: tauri::AppHandle, handle: &ServerHandle, restarts: u32) {
+    spawn_health_poller(app.clone(), handle, restarts);
+    let child = Arc::clone(&handle.child);
+    let stopping = Arc::clone(&handle.stopping);
+    let exited = Arc::clone(&handle.exited);
+    let pid_file = handle.pid_file.clone();
+    let id = handle.id;
+    let config = handle.config.clone();
+    let started_at = handle.started_at;
+
+    tauri::async_runtime::spawn(async move {
+        let status = loop {
+            tokio::time::sleep(CRASH_POLL_INTERVAL).await;
+            // Only hold the child lock for try_wait, never while taking the
+            // state lock below
+            match child.lock().await.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::error!("Lost track of server process: {}", e);
+                    return;
+                }
+            }
+        };
+
+        exited.store(true, Ordering::SeqCst);
+        if stopping.load(Ordering::SeqCst) {
+            return;
+        }
+        // A restart writes a fresh one
+        if let Some(path) = &pid_file {
+            remove_pid_file(path);
+        }
+
+        let state = app.state::<AppState>();
+        if status.success() {
+            tracing::info!("Server exited cleanly");
+            if clear_if_current(&state, id).await {
+                let status = ServerStatus::of(None, &state.server_history).exited_with(status);
+                events::emit_server_changed(&app, &status);
+            }
+            return;
+        }
+
+        tracing::error!("Server crashed: {}", status);
+        let last_logs = state.server_logs.recent(CRASH_LOG_LINES);
+        if let Some(limit) = config.max_memory_mb {
+            if ran_out_of_memory(&last_logs) {
+                tracing::error!("Server ran out of memory (limit {} MB)", limit);
+                let _ = app.emit(
+                    "server-oom",
+                    serde_json::json!({
+                        "exit_code": status.code(),
+                        "max_memory_mb": limit,
+                        "uptime_secs": started_at.elapsed().as_secs(),
+                        "last_logs": last_logs,
+                    }),
+                );
+            }
+        }
+        let _ = app.emit(
+            "server-crashed",
+            serde_json::json!({
+                "exit_code": status.code(),
+                "last_logs": last_logs,
+            }),
+        );
+
+        // A long healthy run means this is a new problem, not the same one
+        let attempts = if started_at.elapsed() >= STABLE_UPTIME { 0 } else { restarts };
+        let reason = format!("Crashed ({})", status);
+        restart_after_failure(&app, id, &config, attempts, started_at.elapsed(), reason, Some(status)).await;
+    });
+}
+
+/// Restart the server that the handle with `id` was running, backing off
+/// between attempts until `max_restarts` is reached. `attempts` is how many
+/// restarts already happened in a row, `uptime` how long the failed server
+/// ran and `reason` goes into the history.
+///
+/// On success the new handle replaces the old one in state and gets its own
+/// crash watcher. Otherwise the state is cleared and `server-failed` is
+/// emitted, or `server-circuit-open` when the circuit breaker stopped the
+/// restart. Returns whether the server came back.
+async fn restart_after_failure(
+    app: &tauri::AppHandle,
+    id: u64,
+    config: &ServerConfig,
+    mut attempts: u32,
+    uptime: Duration,
+    reason: String,
+    exit: Option<ExitStatus>,
+) -> bool {
+    let state = app.state::<AppState>();
+    if let Some((generation, failures)) = state.server_circuit.record_failure(uptime, config) {
+        clear_after_failure(app, &state, id, exit).await;
+        open_circuit(app, config.clone(), generation, failures);
+        return false;
+    }
+    if attempts < config.max_restarts {
+        tray::set_server_state(app, ServiceState::Starting);
+    }
+    while attempts < config.max_restarts {
+        let backoff = config
+            .restart_backoff
+            .saturating_mul(2u32.saturating_pow(attempts))
+            .min(config.max_restart_backoff);
+        tokio::time::sleep(backoff).await;
+        attempts += 1;
+
+        // Stopped or replaced by a command while we were waiting
+        if state.server.lock().await.as_ref().map(|h| h.id) != Some(id) {
+            return false;
+        }
+
+        tracing::info!(
+            "Restarting server (attempt {}/{})",
+            attempts, config.max_restarts
+        );
+        notification::show(
+            app,
+            if exit.is_some() { "Server crashed" } else { "Server unresponsive" },
+            format!("Restarting... (attempt {}/{})", attempts, config.max_restarts),
+        );
+        // Not under the state lock, which would block every command for
+        // the whole startup
+        match start(config.clone(), state.server_logs.clone()).await {
+            Ok(new_handle) => {
+                let mut server_state = state.server.lock().await;
+                // Stopped or replaced while this one was starting
+                if server_state.as_ref().map(|h| h.id) != Some(id) {
+                    drop(server_state);
+                    if let Err(e) = stop(new_handle).await {
+                        tracing::warn!("Failed to stop superseded restart: {}", e);
+                    }
+                    return false;
+                }
+                state.server_history.record(reason);
+                spawn_crash_watcher_with_count(app.clone(), &new_handle, attempts);
+                state.last_server_port.store(new_handle.port, Ordering::SeqCst);
+                notification::show(
+                    app,
+                    "Server restarted",
+                    format!("Server restarted successfully on port {}", new_handle.port),
+                );
+                *server_state = Some(new_handle);
+                let status = ServerStatus::of(server_state.as_ref(), &state.server_history);
+                events::emit_server_changed(app, &status);
+                return true;
+            }
+            Err(e) => tracing::error!("Server restart failed: {}", e),
+        }
+    }
+
+    clear_after_failure(app, &state, id, exit).await;
+    let _ = app.emit(
+        "server-failed",
+        serde_json::json!({ "restarts": attempts }),
+    );
+    false
 }
 
-// Path to the bundled Node.js server executable
-fn get_server_path() -> PathBuf {
-    let exe_path = std::env::current_exe()
-        .expect("Failed to get exe path");
+/// Drop the failed handle with `id` from state and tell the frontend.
+async fn clear_after_failure(app: &tauri::AppHandle, state: &AppState, id: u64, exit: Option<ExitStatus>) {
+    if clear_if_current(state, id).await {
+        let status = ServerStatus::of(None, &state.server_history);
+        let status = match exit {
+            Some(exit) => status.exited_with(exit),
+            None => status,
+        };
+        events::emit_server_changed(app, &status);
+    }
+}
 
-    let exe_dir = exe_path.parent().expect("Failed to get exe directory");
+/// Report the circuit as open and, after `circuit_half_open_delay`, try one
+/// restart unless the circuit was reset or a server was started meanwhile.
+fn open_circuit(app: &tauri::AppHandle, config: ServerConfig, generation: u64, failures: u32) {
+    tracing::error!(
+        "Server failed {} times in a row shortly after starting, not restarting it",
+        failures
+    );
+    let _ = app.emit("server-circuit-open", serde_json::json!({ "failures": failures }));
+    notification::show(
+        app,
+        "Server keeps crashing",
+        format!("Stopped restarting after {} crashes in a row", failures),
+    );
 
-    // In development/release builds, go up from target/debug or target/release to src-tauri
-    let mut current: PathBuf = exe_dir.to_path_buf();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(config.circuit_half_open_delay).await;
+        let state = app.state::<AppState>();
+        if state.server.lock().await.is_some() || !state.server_circuit.half_open(generation) {
+            return;
+        }
 
-    // Try going up multiple levels to find src-tauri
-    for _ in 0..5 {
-        // Check if src-tauri exists at this level
-        let src_tauri = current.join("src-tauri");
-        if src_tauri.exists() {
-            let server_path = src_tauri.join("resources").join("server").join("index.js");
-            if server_path.exists() {
-                return server_path;
+        tracing::info!("Trying one server restart after the circuit opened");
+        tray::set_server_state(&app, ServiceState::Starting);
+        match start(config.clone(), state.server_logs.clone()).await {
+            Ok(new_handle) => {
+                let mut server_state = state.server.lock().await;
+                // Started by a command while this one was starting
+                if server_state.is_some() {
+                    drop(server_state);
+                    if let Err(e) = stop(new_handle).await {
+                        tracing::warn!("Failed to stop superseded restart: {}", e);
+                    }
+                    return;
+                }
+                state.server_history.record("Circuit half-open".to_string());
+                spawn_crash_watcher(app.clone(), &new_handle);
+                state.last_server_port.store(new_handle.port, Ordering::SeqCst);
+                *server_state = Some(new_handle);
+                let status = ServerStatus::of(server_state.as_ref(), &state.server_history);
+                events::emit_server_changed(&app, &status);
+            }
+            Err(e) => {
+                tracing::error!("Server restart failed: {}", e);
+                events::emit_server_changed(&app, &ServerStatus::of(None, &state.server_history));
+                let reopened = state.server_circuit.record_failure(Duration::ZERO, &config);
+                if let Some((generation, failures)) = reopened {
+                    open_circuit(&app, config, generation, failures);
+                }
             }
         }
+    });
+}
 
-        // Check if resources/server exists directly at this level
-        let server_path = current.join("resources").join("server").join("index.js");
-        if server_path.exists() {
-            return server_path;
+/// Check the health endpoint every `health_poll_interval` while the server
+/// runs, emitting `server-healthy` or `server-unhealthy` after each check.
+///
+/// A server still running but failing `health_failure_threshold` checks in
+/// a row is treated as frozen: `server-frozen` is emitted, it is killed and
+/// restarted as after a crash, and `server-restarted-after-health-failure`
+/// is emitted once it is back. No checks run while the watchdog is paused.
+/// Polling ends when the server is stopped or exits, which the crash
+/// watcher handles.
+fn spawn_health_poller(app: tauri::AppHandle, handle: &ServerHandle, restarts: u32) {
+    let Some(interval) = handle.config.health_poll_interval else {
+        return;
+    };
+    let threshold = handle.config.health_failure_threshold.max(1);
+    let stopping = Arc::clone(&handle.stopping);
+    let exited = Arc::clone(&handle.exited);
+    let watchdog = Arc::clone(&handle.watchdog);
+    let id = handle.id;
+    let port = handle.port;
+    let config = handle.config.clone();
+    let started_at = handle.started_at;
+
+    tauri::async_runtime::spawn(async move {
+        let mut failures: u8 = 0;
+        while failures < threshold {
+            tokio::time::sleep(interval).await;
+            if stopping.load(Ordering::SeqCst) || exited.load(Ordering::SeqCst) {
+                return;
+            }
+            if watchdog.paused() {
+                failures = 0;
+                continue;
+            }
+            let result = probe_server_health(port, &config.health_check, 1, Duration::ZERO).await;
+            // A pause that started during the check wins
+            if watchdog.paused() {
+                failures = 0;
+                continue;
+            }
+            match result {
+                Ok(()) => {
+                    failures = 0;
+                    watchdog.failures.store(0, Ordering::Relaxed);
+                    *watchdog.last_success.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+                    let _ = app.emit("server-healthy", serde_json::json!({ "port": port }));
+                }
+                Err(e) => {
+                    failures += 1;
+                    watchdog.failures.store(failures as u32, Ordering::Relaxed);
+                    tracing::warn!("Server health check failed ({}/{}): {}", failures, threshold, e);
+                    let _ = app.emit(
+                        "server-unhealthy",
+                        serde_json::json!({
+                            "port": port,
+                            "consecutive_failures": failures,
+                            "error": e.to_string(),
+                        }),
+                    );
+                }
+            }
         }
 
-        // Go up one level
-        match current.parent() {
-            Some(p) => current = p.to_path_buf(),
-            None => break,
+        let state = app.state::<AppState>();
+        let (shutdown, pid_file) = {
+            let server_state = state.server.lock().await;
+            // Stopped, replaced, or already exited and left to the crash
+            // watcher
+            let Some(handle) = server_state.as_ref().filter(|h| h.id == id) else {
+                return;
+            };
+            if handle.exited.load(Ordering::SeqCst) {
+                return;
+            }
+            // Marked before the lock is released, so neither the crash
+            // watcher nor a stop command acts on the exit as well
+            handle.stopping.store(true, Ordering::SeqCst);
+            (Shutdown::of(handle), handle.pid_file.clone())
         };
+        tracing::error!("Server failed {} health checks in a row, restarting it", failures);
+        let _ = app.emit("server-frozen", serde_json::json!({ "failures": failures }));
+        if let Err(e) = shutdown.run().await {
+            tracing::warn!("Failed to stop unresponsive server: {}", e);
+        }
+        if let Some(path) = &pid_file {
+            remove_pid_file(path);
+        }
+        // The killed handle stays in state through the backoff so commands
+        // can still supersede the restart, but nothing is running any more.
+        // Every path out of `restart_after_failure` reports the outcome.
+        events::emit_server_changed(&app, &ServerStatus::of(None, &state.server_history));
+        tray::set_server_state(&app, ServiceState::Starting);
+
+        let attempts = if started_at.elapsed() >= STABLE_UPTIME { 0 } else { restarts };
+        let reason = format!("Unresponsive ({} failed health checks)", failures);
+        if restart_after_failure(&app, id, &config, attempts, started_at.elapsed(), reason, None).await {
+            let _ = app.emit(
+                "server-restarted-after-health-failure",
+                serde_json::json!({ "failed_checks": failures }),
+            );
+        }
+    });
+}
+
+/// Drop the handle from state if it is still the one with `id`. Returns
+/// whether it was.
+async fn clear_if_current(state: &AppState, id: u64) -> bool {
+    let mut server_state = state.server.lock().await;
+    let current = server_state.as_ref().map(|h| h.id) == Some(id);
+    if current {
+        *server_state = None;
     }
+    current
+}
 
-    // Fallback to exe_dir/resources/server
-    exe_dir.join("resources").join("server").join("index.js")
+/// Stop the server and everything it spawned, giving it a chance to shut
+/// down cleanly first.
+///
+/// On Unix the process group gets SIGTERM (the server saves its terminal
+/// buffers on SIGTERM) and is only killed if the server is still running
+/// after the handle's shutdown timeout. Windows has no equivalent signal
+/// for a windowless child, so it is terminated straight away.
+pub async fn stop(handle: ServerHandle) -> Result<(), AppError> {
+    let result = terminate(&handle).await;
+    if let Some(path) = &handle.pid_file {
+        remove_pid_file(path);
+    }
+    result
 }
 
-pub async fn start(port: u16) -> Result<ServerHandle, String> {
-    // Check if we're in development mode
-    if is_development_mode() {
-        start_dev_server(port).await
-    } else {
-        start_production_server(port).await
+async fn terminate(handle: &ServerHandle) -> Result<(), AppError> {
+    Shutdown::of(handle).run().await
+}
+
+/// What `terminate` needs from a handle, cloned out of it so the shutdown
+/// can run after the state lock is released.
+struct Shutdown {
+    child: Arc<TokioMutex<Child>>,
+    group: Option<Arc<ProcessGroup>>,
+    stopping: Arc<AtomicBool>,
+    // Only Unix has a graceful signal to wait on
+    #[cfg_attr(not(unix), allow(dead_code))]
+    shutdown_timeout: Duration,
+}
+
+impl Shutdown {
+    fn of(handle: &ServerHandle) -> Self {
+        Self {
+            child: Arc::clone(&handle.child),
+            group: handle.group.clone(),
+            stopping: Arc::clone(&handle.stopping),
+            shutdown_timeout: handle.shutdown_timeout,
+        }
+    }
+
+    async fn run(&self) -> Result<(), AppError> {
+        self.stopping.store(true, Ordering::SeqCst);
+        let mut child = self.child.lock().await;
+
+        // Already exited (crashed, or reaped by the crash watcher), but its
+        // workers may not have
+        if let Ok(Some(_)) = child.try_wait() {
+            if let Some(group) = &self.group {
+                group.kill();
+            }
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        if let Some(pid) = child.id() {
+            match &self.group {
+                Some(group) => group.terminate(),
+                // SAFETY: kill(2) has no memory-safety requirements; the worst
+                // case for a stale pid is an ESRCH error, which the wait below
+                // handles
+                None => unsafe {
+                    libc::kill(pid as libc::pid_t, libc::SIGTERM);
+                },
+            }
+            if tokio::time::timeout(self.shutdown_timeout, child.wait())
+                .await
+                .is_ok()
+            {
+                return Ok(());
+            }
+            tracing::warn!(
+                "Server did not exit within {}s of SIGTERM, killing it",
+                self.shutdown_timeout.as_secs()
+            );
+        }
+
+        if let Some(group) = &self.group {
+            group.kill();
+        }
+        child.kill()
+            .await
+            .map_err(|e| format!("Failed to stop server: {}", e))?;
+        Ok(())
     }
 }
 
-pub async fn stop(mut handle: ServerHandle) -> Result<(), String> {
-    handle.child.kill()
-        .await
-        .map_err(|e| format!("Failed to stop server: {}", e))?;
-    Ok(())
+/// Environment variables whose name contains one of these have their
+/// value masked in logs
+const SENSITIVE_ENV_MARKERS: &[&str] = &["SECRET", "TOKEN", "KEY", "PASSWORD"];
+
+/// Whether an environment variable called `name` probably holds a secret.
+pub fn is_sensitive_env(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    SENSITIVE_ENV_MARKERS.iter().any(|marker| upper.contains(marker))
+}
+
+/// `env` as sorted `NAME=value` pairs for logging, with sensitive values
+/// replaced by `****`.
+pub fn mask_env(env: &HashMap<String, String>) -> String {
+    if env.is_empty() {
+        return "(none)".to_string();
+    }
+    let mut pairs: Vec<String> = env
+        .iter()
+        .map(|(name, value)| {
+            if is_sensitive_env(name) {
+                format!("{}=****", name)
+            } else {
+                format!("{}={}", name, value)
+            }
+        })
+        .collect();
+    pairs.sort();
+    pairs.join(", ")
+}
+
+/// Per-request timeout for `probe_server_health`
+const HEALTH_REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// Path checked when none is configured
+pub const DEFAULT_HEALTH_CHECK_PATH: &str = "/health";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HealthCheckMethod {
+    #[default]
+    Get,
+    /// For servers whose health endpoint is expensive to render
+    Head,
+}
+
+/// The request `probe_server_health` makes and what a passing answer is.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    pub path: String,
+    pub method: HealthCheckMethod,
+    /// Besides a 2xx status, the body must contain this text
+    pub expected_body_contains: Option<String>,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        HealthCheck {
+            path: DEFAULT_HEALTH_CHECK_PATH.to_string(),
+            method: HealthCheckMethod::default(),
+            expected_body_contains: None,
+        }
+    }
+}
+
+/// Make `check`'s request up to `retries` times, `delay` apart, until the
+/// server passes it.
+pub async fn probe_server_health(
+    port: u16,
+    check: &HealthCheck,
+    retries: u8,
+    delay: Duration,
+) -> Result<(), AppError> {
+    let client = reqwest::Client::builder()
+        .timeout(HEALTH_REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let url = format!("http://localhost:{}/{}", port, check.path.trim_start_matches('/'));
+
+    let mut last_error = String::from("no attempts made");
+    for attempt in 0..retries {
+        if attempt > 0 {
+            tokio::time::sleep(delay).await;
+        }
+        let request = match check.method {
+            HealthCheckMethod::Get => client.get(&url),
+            HealthCheckMethod::Head => client.head(&url),
+        };
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                let Some(expected) = &check.expected_body_contains else {
+                    return Ok(());
+                };
+                match response.text().await {
+                    Ok(body) if body.contains(expected.as_str()) => return Ok(()),
+                    Ok(_) => last_error = format!("response did not contain '{}'", expected),
+                    Err(e) => last_error = e.to_string(),
+                }
+            }
+            Ok(response) => last_error = format!("HTTP {}", response.status().as_u16()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+
+    Err(AppError::HealthCheckFailed { url, attempts: retries, last_error })
+}
+
+/// Whether systemd started this process with pre-bound sockets
+/// (`LISTEN_FDS`/`LISTEN_PID` addressed to our PID).
+#[cfg(target_os = "linux")]
+pub fn is_socket_activated() -> bool {
+    let for_this_process = std::env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        == Some(std::process::id());
+    let fd_count = std::env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|n| n.parse::<u32>().ok())
+        .unwrap_or(0);
+    for_this_process && fd_count > 0
+}
+
+/// The inherited listening socket to hand to Node.js, if socket activated.
+#[cfg(target_os = "linux")]
+pub fn socket_activation_fd() -> Option<i32> {
+    is_socket_activated().then_some(SD_LISTEN_FDS_START)
+}
+
+/// Pass a systemd-provided socket through to Node.js via `NODE_SOCKET_FD`.
+/// The fd is inherited by the child as-is; systemd does not set CLOEXEC.
+#[cfg(target_os = "linux")]
+fn apply_socket_activation(command: &mut Command) {
+    if let Some(fd) = socket_activation_fd() {
+        command.env("NODE_SOCKET_FD", fd.to_string());
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn apply_socket_activation(_command: &mut Command) {}
+
+/// Hand the listener from `allocate_port` to Node.js as `NODE_SOCKET_FD`,
+/// like a systemd socket, so the server never binds the port itself.
+#[cfg(unix)]
+fn pass_listener(command: &mut Command, listener: &std::net::TcpListener) {
+    use std::os::fd::AsRawFd;
+
+    let fd = listener.as_raw_fd();
+    command.env("NODE_SOCKET_FD", fd.to_string());
+    // Rust opens sockets close-on-exec. Clearing that in the child only
+    // keeps processes spawned meanwhile from inheriting the port.
+    // SAFETY: fcntl is async-signal-safe, so fine between fork and exec
+    unsafe {
+        command.pre_exec(move || {
+            if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn pass_listener(_command: &mut Command, _listener: &std::net::TcpListener) {}
+
+#[cfg(target_os = "linux")]
+pub fn emit_socket_activation(app: &tauri::AppHandle) {
+    use tauri::Emitter;
+
+    if let Some(fd) = socket_activation_fd() {
+        let _ = app.emit("server://socket-activated", serde_json::json!({ "fd": fd }));
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn emit_socket_activation(_app: &tauri::AppHandle) {}
+
+fn is_port_free(port: u16) -> bool {
+    std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+/// `preferred` if nothing is bound to it, otherwise the first free port in
+/// `range`. The probe listener is closed before returning, so another
+/// process could claim the port before Node.js binds it.
+pub fn find_available_port(preferred: u16, range: RangeInclusive<u16>) -> Result<u16, AppError> {
+    if is_port_free(preferred) {
+        return Ok(preferred);
+    }
+    let (first, last) = (*range.start(), *range.end());
+    range
+        .filter(|&port| port != 0 && port != preferred)
+        .find(|&port| is_port_free(port))
+        .ok_or_else(|| AppError::Other(format!(
+            "Port {} is in use and no free port was found in {}-{}",
+            preferred, first, last
+        )))
+}
+
+/// Up to `count` free ports at or above `start`, in ascending order.
+pub fn available_ports(start: u16, count: usize) -> Vec<u16> {
+    (start.max(1)..=u16::MAX)
+        .filter(|&port| is_port_free(port))
+        .take(count)
+        .collect()
+}
+
+/// A free loopback port chosen by the OS. On Unix its listener comes back
+/// too, for `pass_listener` to hand to Node.js, so nothing can take the port
+/// in between. Windows can't pass a socket that way, so the listener is
+/// closed here (Node's own listen() would fail while it is open) and there
+/// is a short window where another process could claim the port first.
+fn allocate_port() -> Result<(u16, Option<std::net::TcpListener>), String> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to allocate a free port: {}", e))?;
+    let port = listener.local_addr()
+        .map_err(|e| format!("Failed to read allocated port: {}", e))?
+        .port();
+    Ok((port, cfg!(unix).then_some(listener)))
 }
 
 fn is_development_mode() -> bool {
@@ -70,90 +1943,784 @@ fn is_development_mode() -> bool {
             .unwrap_or(false)
 }
 
-async fn start_dev_server(port: u16) -> Result<ServerHandle, String> {
+async fn start_dev_server(
+    port: u16,
+    listener: Option<&std::net::TcpListener>,
+    config: &ServerConfig,
+) -> Result<Child, AppError> {
     // Find the project root (where package.json exists)
-    let project_root = find_project_root()
+    let project_root = paths::find_project_root()
         .map_err(|e| format!("Failed to find project root: {}", e))?;
 
-    let server_dir = project_root.join("apps").join("server");
+    tracing::info!(
+        "Using {:?} workspace at {}",
+        detect_workspace_tool(&project_root),
+        project_root.display()
+    );
+
+    let server_dir = config.working_dir
+        .clone()
+        .unwrap_or_else(|| project_root.join("apps").join("server"));
+
+    let manager = config.package_manager.unwrap_or_else(|| detect_package_manager(&server_dir));
+    if manager == PackageManager::Npm {
+        if let Some(required) = engine_requirements().and_then(|e| e.npm) {
+            check_engine("npm", &PackageManager::Npm.resolve()?, &required).await?;
+        }
+    }
 
-    // Use npm to run the server in development mode
-    let child = Command::new("npm")
+    let env_file = env::server_env_file(&server_dir, &config.env)?;
+
+    // Run the server's dev script in development mode
+    let mut command = Command::new(manager.resolve()?);
+    manager.run_script(&mut command, "dev", &config.extra_args);
+    command
         .current_dir(&server_dir)
-        .arg("run")
-        .arg("dev")
+        .envs(&env_file)
+        .envs(&config.env)
+        .env("PORT", port.to_string())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if config.capture_stdin {
+        command.stdin(Stdio::piped());
+    }
+    apply_socket_activation(&mut command);
+    if let Some(listener) = listener {
+        pass_listener(&mut command, listener);
+    }
+    ProcessGroup::prepare(&mut command);
+    config.limits().apply(&mut command);
+
+    let child = command
         .spawn()
-        .map_err(|e| format!("Failed to start dev server: {}. Ensure npm is in PATH", e))?;
+        .inspect_err(|e| {
+            tracing::error!(
+                "Failed to start dev server: {}. Ensure {} is in PATH",
+                e,
+                manager.program()
+            )
+        })?;
 
-    Ok(ServerHandle { child, port })
+    Ok(child)
 }
 
-async fn start_production_server(port: u16) -> Result<ServerHandle, String> {
-    let server_path = get_server_path();
+async fn start_production_server(
+    port: u16,
+    listener: Option<&std::net::TcpListener>,
+    config: &ServerConfig,
+) -> Result<Child, AppError> {
+    let server_path = config.script.clone().unwrap_or_else(get_server_path);
 
     if !server_path.exists() {
-        return Err(format!(
+        return Err(AppError::Other(format!(
             "Server executable not found at: {}. Ensure resources are bundled correctly.",
             server_path.display()
-        ));
+        )));
     }
 
     // Use Node.js to run the bundled server
-    let node_path = find_node_executable()?;
+    let node_path = resolve_node_binary(config.node_binary.as_deref())?;
+
+    if let Some(required) = engine_requirements().and_then(|e| e.node) {
+        check_engine("Node.js", &node_path, &required).await?;
+    }
 
     // Convert paths to strings (don't canonicalize to avoid path issues)
     let node_exe = node_path.to_string_lossy().to_string();
     let server_script = server_path.to_string_lossy().to_string();
 
-    let child = Command::new(&node_exe)
+    // The server runs from its own directory unless told otherwise, so
+    // that's where its .env lives
+    let env_file = match config.working_dir.as_deref().or(server_path.parent()) {
+        Some(dir) => env::server_env_file(dir, &config.env)?,
+        None => HashMap::new(),
+    };
+
+    let mut command = Command::new(&node_exe);
+    command
         .arg(&server_script)
+        .args(&config.extra_args)
+        .envs(&env_file)
+        .envs(&config.env)
         .env("PORT", port.to_string())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = &config.working_dir {
+        command.current_dir(dir);
+    }
+    if config.capture_stdin {
+        command.stdin(Stdio::piped());
+    }
+    apply_socket_activation(&mut command);
+    if let Some(listener) = listener {
+        pass_listener(&mut command, listener);
+    }
+    ProcessGroup::prepare(&mut command);
+    config.limits().apply(&mut command);
+
+    let child = command
+        .spawn()
+        .inspect_err(|e| tracing::error!("Failed to start server: {} (node: '{}', script: '{}')", e, node_exe, server_script))?;
+
+    Ok(child)
+}
+
+/// The `engines` field of the server's package.json.
+#[derive(Clone, serde::Serialize)]
+pub struct NodeEngine {
+    pub node: Option<String>,
+    pub npm: Option<String>,
+}
+
+pub fn engine_requirements() -> Option<NodeEngine> {
+    ENGINE_REQUIREMENTS
+        .get_or_init(|| read_engines(&server_package_json()?))
+        .clone()
+}
+
+fn server_package_json() -> Option<PathBuf> {
+    if is_development_mode() {
+        let root = paths::find_project_root().ok()?;
+        Some(root.join("apps").join("server").join("package.json"))
+    } else {
+        Some(get_server_path().parent()?.join("package.json"))
+    }
+}
+
+fn read_engines(package_json: &Path) -> Option<NodeEngine> {
+    let content = std::fs::read_to_string(package_json).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let engines = json.get("engines")?;
+    let field = |name: &str| engines.get(name).and_then(|v| v.as_str()).map(String::from);
+    Some(NodeEngine {
+        node: field("node"),
+        npm: field("npm"),
+    })
+}
+
+/// Whether the server's `node_modules` is in place.
+#[derive(serde::Serialize)]
+pub struct DependencyStatus {
+    /// Every dependency in package.json has a directory in `node_modules`
+    pub installed: bool,
+    pub node_modules_exists: bool,
+    /// Unix time in seconds `node_modules` was last modified
+    pub last_install_timestamp: Option<u64>,
+}
+
+pub fn dependency_status(server_dir: &Path) -> DependencyStatus {
+    let node_modules = server_dir.join("node_modules");
+    let last_install_timestamp = std::fs::metadata(&node_modules)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let node_modules_exists = node_modules.is_dir();
+
+    let installed = node_modules_exists
+        && read_dependency_names(&server_dir.join("package.json"))
+            .is_some_and(|names| names.iter().all(|name| node_modules.join(name).exists()));
+
+    DependencyStatus {
+        installed,
+        node_modules_exists,
+        last_install_timestamp,
+    }
+}
+
+fn read_dependency_names(package_json: &Path) -> Option<Vec<String>> {
+    let content = std::fs::read_to_string(package_json).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let names = ["dependencies", "devDependencies"]
+        .iter()
+        .filter_map(|field| json.get(field)?.as_object())
+        .flat_map(|deps| deps.keys().cloned())
+        .collect();
+    Some(names)
+}
+
+/// Tail of installer stderr kept for the error message
+const INSTALL_ERROR_LINES: usize = 50;
+
+/// Run `<package manager> install` in `server_dir`, emitting every output
+/// line as an `install-log` event (`{ "stream": "stdout", "line": "..." }`).
+pub async fn install_dependencies(app: &tauri::AppHandle, server_dir: &Path) -> Result<(), AppError> {
+    let manager = detect_package_manager(server_dir);
+    let program = manager.program();
+    tracing::info!("Running {} install in {}", program, server_dir.display());
+
+    let mut child = Command::new(manager.resolve()?)
+        .arg("install")
+        .current_dir(server_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .inspect_err(|e| tracing::error!("Failed to run {} install: {}. Ensure it is in PATH", program, e))?;
+
+    let stdout = child.stdout.take().ok_or("Failed to capture installer output")?;
+    let stderr = child.stderr.take().ok_or("Failed to capture installer output")?;
+    let stdout_task = tokio::spawn(forward_install_output(app.clone(), stdout, "stdout"));
+    let stderr_task = tokio::spawn(forward_install_output(app.clone(), stderr, "stderr"));
+
+    let status = child.wait().await?;
+    let _ = stdout_task.await;
+    let stderr_tail = stderr_task.await.unwrap_or_default();
+
+    if !status.success() {
+        return Err(format!(
+            "{} install failed ({}):\n{}",
+            program,
+            status,
+            stderr_tail.into_iter().collect::<Vec<_>>().join("\n")
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Default limit for `run_server_script`
+pub const DEFAULT_SCRIPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Outcome of `run_server_script`, with its output captured in full.
+#[derive(serde::Serialize)]
+pub struct ExecResult {
+    /// -1 when the script was ended by a signal
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+/// Run the package.json script `script` in `server_dir` with the project's
+/// package manager, `args` passed through and `env` added to the
+/// environment. The script and anything it started are killed if it runs
+/// longer than `timeout`. A script that fails still returns its result;
+/// only failing to run it (or the timeout) is an error.
+pub async fn run_server_script(
+    server_dir: &Path,
+    script: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    timeout: Duration,
+) -> Result<ExecResult, AppError> {
+    let manager = detect_package_manager(server_dir);
+    tracing::info!("Running {} script '{}' in {}", manager.program(), script, server_dir.display());
+
+    let mut command = Command::new(manager.resolve()?);
+    manager.run_script(&mut command, script, args);
+    command
+        .current_dir(server_dir)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // npm runs the script in a shell, which may start more processes
+    ProcessGroup::prepare(&mut command);
+
+    let started = Instant::now();
+    let mut child = command
         .spawn()
-        .map_err(|e| format!("Failed to start server: {} (node: '{}', script: '{}')", e, node_exe, server_script))?;
+        .inspect_err(|e| tracing::error!("Failed to run {}: {}. Ensure it is in PATH", manager.program(), e))?;
+    let group = ProcessGroup::attach(&child, &ProcessLimits::default())
+        .inspect_err(|e| tracing::warn!("Processes started by '{}' won't be stopped with it: {}", script, e))
+        .ok();
+
+    let stdout = child.stdout.take().ok_or("Failed to capture script output")?;
+    let stderr = child.stderr.take().ok_or("Failed to capture script output")?;
+    let kill_group = || group.iter().for_each(ProcessGroup::kill);
+
+    // The output is read under the same timeout: a process the script left
+    // behind would otherwise keep the pipes open indefinitely
+    let run = async {
+        let exited = async {
+            let status = child.wait().await;
+            // Stop whatever the script left running, which also closes
+            // the pipes it inherited
+            kill_group();
+            status
+        };
+        tokio::join!(exited, read_all(stdout), read_all(stderr))
+    };
+    let (status, stdout, stderr) = match tokio::time::timeout(timeout, run).await {
+        Ok((status, stdout, stderr)) => (status?, stdout, stderr),
+        Err(_) => {
+            kill_group();
+            let _ = child.kill().await;
+            return Err(AppError::Timeout { operation: format!("Script '{}'", script), after: timeout });
+        }
+    };
 
-    Ok(ServerHandle { child, port })
+    Ok(ExecResult {
+        exit_code: status.code().unwrap_or(-1),
+        stdout,
+        stderr,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
 }
 
-fn find_node_executable() -> Result<PathBuf, String> {
-    // TEMP: Return hardcoded path for now
-    // TODO: Make this dynamic after verifying it works
-    Ok(PathBuf::from(r"C:\Program Files\nodejs\node.exe"))
+/// Everything `stream` produces, read as (lossy) UTF-8.
+async fn read_all<R: AsyncRead + Unpin>(mut stream: R) -> String {
+    use tokio::io::AsyncReadExt;
+
+    let mut bytes = Vec::new();
+    let _ = stream.read_to_end(&mut bytes).await;
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Emit each line of `stream` as an `install-log` event. Returns the last
+/// `INSTALL_ERROR_LINES` lines.
+async fn forward_install_output<R: AsyncRead + Unpin>(
+    app: tauri::AppHandle,
+    stream: R,
+    name: &'static str,
+) -> VecDeque<String> {
+    let mut tail = VecDeque::with_capacity(INSTALL_ERROR_LINES);
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if name == "stderr" {
+            tracing::warn!(target: "server", "{}", line);
+        } else {
+            tracing::info!(target: "server", "{}", line);
+        }
+        let _ = app.emit(
+            "install-log",
+            serde_json::json!({ "stream": name, "line": line }),
+        );
+        if tail.len() == INSTALL_ERROR_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+    tail
+}
+
+/// Trimmed output of `<program> --version`, e.g. `v20.11.0`.
+pub async fn program_version(program: &Path) -> Result<String, AppError> {
+    let output = Command::new(program).arg("--version").output().await?;
+    if !output.status.success() {
+        return Err(format!("{} --version exited with {}", program.display(), output.status).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Run `<program> --version` and check it against an npm semver range.
+async fn check_engine(tool: &str, program: &Path, required: &str) -> Result<(), AppError> {
+    let found = program_version(program).await?;
+    let version = semver::Version::parse(found.trim_start_matches('v'))
+        .map_err(|e| format!("Could not parse {} version '{}': {}", tool, found, e))?;
+
+    match satisfies_npm_range(&version, required) {
+        Some(true) => Ok(()),
+        Some(false) => Err(format!(
+            "{} version mismatch: server requires {}, found {}",
+            tool, required, found
+        ).into()),
+        None => {
+            tracing::warn!(
+                "Skipping {} version check: unsupported engines range '{}'",
+                tool, required
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Match `version` against an npm-style range. npm separates alternatives
+/// with `||` and comparators with spaces (allowing one between an operator
+/// and its version), and a bare version means an exact match or, when
+/// partial, any version starting with it; the semver crate wants commas and
+/// treats bare versions as `^`. Returns `None` for ranges that cannot be
+/// translated (e.g. hyphen ranges).
+fn satisfies_npm_range(version: &semver::Version, range: &str) -> Option<bool> {
+    let is_operator = |token: &str| !token.is_empty() && token.chars().all(|ch| "<>=~^".contains(ch));
+    let mut satisfied = false;
+    for alternative in range.split("||") {
+        let mut comparators: Vec<String> = Vec::new();
+        let mut operator: Option<&str> = None;
+        for token in alternative.split_whitespace() {
+            if token == "-" {
+                return None;
+            }
+            if is_operator(token) {
+                operator = Some(token);
+                continue;
+            }
+            let token = token.trim_start_matches('v');
+            let comparator = match operator.take() {
+                Some(operator) => format!("{}{}", operator, token),
+                None if token.starts_with(|ch: char| ch.is_ascii_digit()) && !token.contains(['x', 'X', '*']) => {
+                    // `18.2` is npm's `18.2.x`, which is semver's `~18.2`
+                    if token.matches('.').count() >= 2 { format!("={}", token) } else { format!("~{}", token) }
+                }
+                None => token.to_string(),
+            };
+            comparators.push(comparator);
+        }
+        // A trailing operator with no version
+        if operator.is_some() {
+            return None;
+        }
+        let req = if comparators.is_empty() { "*".to_string() } else { comparators.join(", ") };
+        satisfied |= semver::VersionReq::parse(&req).ok()?.matches(version);
+    }
+    Some(satisfied)
 }
 
-fn find_project_root() -> Result<PathBuf, String> {
-    let current_dir = std::env::current_dir()
-        .map_err(|e| format!("Failed to get current dir: {}", e))?;
+/// The configured Node.js binary if there is one, otherwise whatever
+/// `find_node_executable` turns up.
+pub fn resolve_node_binary(configured: Option<&Path>) -> Result<PathBuf, AppError> {
+    match configured {
+        Some(path) if path.is_file() => Ok(path.to_path_buf()),
+        Some(path) => Err(AppError::NodeNotFound { searched_paths: vec![path.to_path_buf()] }),
+        None => find_node_executable(),
+    }
+}
 
-    let mut path = current_dir;
+pub fn find_node_executable() -> Result<PathBuf, AppError> {
+    if let Ok(path) = which::which("node") {
+        return Ok(path);
+    }
 
-    // Search up for package.json
-    for _ in 0..10 {
-        let package_json = path.join("package.json");
-        if package_json.exists() {
-            return Ok(path);
+    // GUI launches often don't inherit the shell's PATH, so fall back to
+    // the places installers and version managers usually put node
+    let mut searched = Vec::new();
+    for location in node_install_locations() {
+        match location {
+            NodeLocation::Exact(path) => {
+                if is_executable(&path) {
+                    return Ok(path);
+                }
+                searched.push(path);
+            }
+            NodeLocation::Versioned { versions_dir, binary } => {
+                for version_dir in version_dirs_newest_first(&versions_dir) {
+                    let path = version_dir.join(&binary);
+                    if is_executable(&path) {
+                        return Ok(path);
+                    }
+                }
+                searched.push(versions_dir.join("*").join(&binary));
+            }
         }
-        if !path.pop() {
-            break;
+    }
+
+    searched.insert(0, PathBuf::from("PATH"));
+    Err(AppError::NodeNotFound { searched_paths: searched })
+}
+
+enum NodeLocation {
+    Exact(PathBuf),
+    /// A version manager directory holding one subdirectory per version
+    Versioned { versions_dir: PathBuf, binary: PathBuf },
+}
+
+#[cfg(windows)]
+fn node_install_locations() -> Vec<NodeLocation> {
+    let env_dir = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    let mut locations = Vec::new();
+
+    if let Some(program_files) = env_dir("ProgramFiles") {
+        locations.push(NodeLocation::Exact(program_files.join("nodejs").join("node.exe")));
+    }
+    if let Some(app_data) = env_dir("APPDATA") {
+        // nvm-windows keeps each version in %APPDATA%\nvm\v<version>
+        locations.push(NodeLocation::Versioned {
+            versions_dir: app_data.join("nvm"),
+            binary: PathBuf::from("node.exe"),
+        });
+    }
+    if let Some(local_app_data) = env_dir("LOCALAPPDATA") {
+        locations.push(NodeLocation::Exact(
+            local_app_data.join("Programs").join("nodejs").join("node.exe"),
+        ));
+    }
+
+    locations
+}
+
+#[cfg(target_os = "macos")]
+fn node_install_locations() -> Vec<NodeLocation> {
+    let mut locations = Vec::new();
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    // The version the user picked with `nvm alias default` beats the newest
+    if let Some(home) = &home {
+        let nvm_dir = home.join(".nvm");
+        if let Some(version_dir) = nvm_default_version_dir(&nvm_dir) {
+            locations.push(NodeLocation::Exact(version_dir.join("bin").join("node")));
         }
+        locations.push(NodeLocation::Versioned {
+            versions_dir: nvm_dir.join("versions").join("node"),
+            binary: PathBuf::from("bin").join("node"),
+        });
+    }
+    locations.push(NodeLocation::Exact(PathBuf::from("/opt/homebrew/bin/node")));
+    locations.push(NodeLocation::Exact(PathBuf::from("/usr/local/bin/node")));
+    if let Some(home) = &home {
+        locations.push(NodeLocation::Exact(home.join(".volta").join("bin").join("node")));
+        locations.push(NodeLocation::Exact(home.join(".asdf").join("shims").join("node")));
+    }
+    // Versioned Homebrew kegs (`brew install node@20`) aren't linked into bin
+    for opt_dir in ["/opt/homebrew/opt", "/usr/local/opt"] {
+        locations.extend(
+            homebrew_node_kegs(Path::new(opt_dir))
+                .into_iter()
+                .map(|keg| NodeLocation::Exact(keg.join("bin").join("node"))),
+        );
+    }
+
+    locations
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn node_install_locations() -> Vec<NodeLocation> {
+    let mut locations = vec![
+        NodeLocation::Exact(PathBuf::from("/usr/bin/node")),
+        NodeLocation::Exact(PathBuf::from("/usr/local/bin/node")),
+    ];
+
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        locations.push(NodeLocation::Versioned {
+            versions_dir: home.join(".nvm").join("versions").join("node"),
+            binary: PathBuf::from("bin").join("node"),
+        });
+        locations.push(NodeLocation::Exact(home.join(".volta").join("bin").join("node")));
     }
 
-    // Fallback: try relative paths from the exe
-    let exe_path = std::env::current_exe()
-        .map_err(|e| format!("Failed to get exe path: {}", e))?;
+    locations
+}
+
+/// The installed version `~/.nvm/alias/default` points at. Only plain
+/// versions (`20`, `v20.11.0`) are understood; named aliases such as
+/// `lts/*` give `None`.
+#[cfg(target_os = "macos")]
+fn nvm_default_version_dir(nvm_dir: &Path) -> Option<PathBuf> {
+    let alias = std::fs::read_to_string(nvm_dir.join("alias").join("default")).ok()?;
+    let alias = alias.trim().trim_start_matches('v');
+    if alias.is_empty() || !alias.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+
+    // `20` matches any v20.x.y; take the newest of those
+    version_dirs_newest_first(&nvm_dir.join("versions").join("node"))
+        .into_iter()
+        .find(|dir| {
+            dir.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.trim_start_matches('v'))
+                .is_some_and(|name| name == alias || name.starts_with(&format!("{}.", alias)))
+        })
+}
+
+/// `node` and `node@<major>` kegs in a Homebrew `opt` directory, newest
+/// first. The unversioned `node` keg is the latest release.
+#[cfg(target_os = "macos")]
+fn homebrew_node_kegs(opt_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(opt_dir) else {
+        return Vec::new();
+    };
+
+    let mut kegs: Vec<(u64, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let major = match name.strip_prefix("node@") {
+                Some(major) => major.parse().ok()?,
+                None if name == "node" => u64::MAX,
+                None => return None,
+            };
+            Some((major, entry.path()))
+        })
+        .collect();
+    kegs.sort_by(|a, b| b.0.cmp(&a.0));
+    kegs.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Subdirectories of a version manager directory, newest version first.
+/// Names that don't parse as versions sort last.
+fn version_dirs_newest_first(versions_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(versions_dir) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<(Option<semver::Version>, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            let version = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| semver::Version::parse(name.trim_start_matches('v')).ok());
+            (version, path)
+        })
+        .collect();
+    dirs.sort_by(|a, b| b.0.cmp(&a.0));
+    dirs.into_iter().map(|(_, path)| path).collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceTool {
+    Npm,
+    Pnpm,
+    Yarn,
+    Turborepo,
+    Unknown,
+}
+
+/// Tool used to install dependencies and run the server's `dev` script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Npm,
+    Yarn,
+    Pnpm,
+    Bun,
+}
 
-    let mut search_path = exe_path;
-    search_path.pop();
+/// Lock files checked for by `detect_package_manager`, in priority order
+const LOCKFILES: &[(&str, PackageManager)] = &[
+    ("yarn.lock", PackageManager::Yarn),
+    ("pnpm-lock.yaml", PackageManager::Pnpm),
+    ("bun.lockb", PackageManager::Bun),
+    ("package-lock.json", PackageManager::Npm),
+];
 
-    // In dev, exe is in target/debug, go up to project root
-    for _ in 0..5 {
-        let package_json = search_path.join("package.json");
-        if package_json.exists() {
-            return Ok(search_path);
+impl PackageManager {
+    pub fn program(self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "bun",
         }
-        if !search_path.pop() {
-            break;
+    }
+
+    /// Full path of the program on PATH. `Command::new` with the bare name
+    /// only finds `.exe` files on Windows, where npm, yarn and pnpm are
+    /// `.cmd` shims.
+    pub fn resolve(self) -> Result<PathBuf, AppError> {
+        which::which(self.program())
+            .map_err(|e| format!("{} not found: {}. Ensure it is in PATH", self.program(), e).into())
+    }
+
+    /// `command` set up to run `script` from package.json with `args`
+    /// passed through to it.
+    fn run_script(self, command: &mut Command, script: &str, args: &[String]) {
+        match self {
+            // npm needs `--` to stop it reading the args itself
+            PackageManager::Npm => {
+                command.arg("run").arg(script);
+                if !args.is_empty() {
+                    command.arg("--").args(args);
+                }
+            }
+            PackageManager::Bun => {
+                command.arg("run").arg(script).args(args);
+            }
+            PackageManager::Yarn | PackageManager::Pnpm => {
+                command.arg(script).args(args);
+            }
         }
     }
+}
+
+/// Package manager for the project in `server_dir`, from the first lock
+/// file found there or at the workspace root. Falls back to npm when there
+/// is none or the matching tool isn't on PATH.
+pub fn detect_package_manager(server_dir: &Path) -> PackageManager {
+    let root = paths::find_project_root().ok();
+    let detected = std::iter::once(server_dir)
+        .chain(root.as_deref())
+        .find_map(|dir| {
+            LOCKFILES
+                .iter()
+                .find(|(file, _)| dir.join(file).exists())
+                .map(|(_, manager)| *manager)
+        })
+        .unwrap_or(PackageManager::Npm);
+
+    if detected != PackageManager::Npm && which::which(detected.program()).is_err() {
+        tracing::warn!(
+            "Project uses {} but it isn't in PATH, falling back to npm",
+            detected.program()
+        );
+        return PackageManager::Npm;
+    }
+    detected
+}
+
+pub fn detect_workspace_tool(root: &Path) -> WorkspaceTool {
+    if root.join("turbo.json").exists() {
+        WorkspaceTool::Turborepo
+    } else if root.join("pnpm-workspace.yaml").exists() {
+        WorkspaceTool::Pnpm
+    } else if root.join("yarn.lock").exists() {
+        WorkspaceTool::Yarn
+    } else if root.join("package-lock.json").exists() {
+        WorkspaceTool::Npm
+    } else {
+        WorkspaceTool::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::satisfies_npm_range;
+
+    fn satisfies(version: &str, range: &str) -> Option<bool> {
+        satisfies_npm_range(&semver::Version::parse(version).unwrap(), range)
+    }
+
+    #[test]
+    fn bare_full_version_is_exact() {
+        assert_eq!(satisfies("18.2.0", "18.2.0"), Some(true));
+        assert_eq!(satisfies("18.2.1", "18.2.0"), Some(false));
+        assert_eq!(satisfies("18.2.0", "v18.2.0"), Some(true));
+    }
+
+    #[test]
+    fn bare_partial_version_matches_its_prefix() {
+        assert_eq!(satisfies("18.2.5", "18.2"), Some(true));
+        assert_eq!(satisfies("18.3.0", "18.2"), Some(false));
+        assert_eq!(satisfies("18.9.1", "18"), Some(true));
+        assert_eq!(satisfies("19.0.0", "18"), Some(false));
+    }
+
+    #[test]
+    fn alternatives() {
+        assert_eq!(satisfies("20.1.0", "^18 || ^20"), Some(true));
+        assert_eq!(satisfies("19.0.0", "^18 || ^20"), Some(false));
+        assert_eq!(satisfies("16.0.0", ">=18 <19 || 16.0.0"), Some(true));
+    }
+
+    #[test]
+    fn hyphen_range_is_unsupported() {
+        assert_eq!(satisfies("18.0.0", "16.0.0 - 20.0.0"), None);
+    }
 
-    Err("Could not find project root (package.json)".to_string())
+    #[test]
+    fn operator_separated_from_version() {
+        assert_eq!(satisfies("18.0.0", ">= 18"), Some(true));
+        assert_eq!(satisfies("17.9.0", ">= 18"), Some(false));
+        assert_eq!(satisfies("18.5.0", ">= 18 < 19"), Some(true));
+        assert_eq!(satisfies("18.0.0", ">="), None);
+    }
 }
@@ -0,0 +1,40 @@
+/// A keyboard shortcut exposed by the app.
+///
+/// `command` identifies the action the shortcut triggers so the frontend
+/// can map an entry back to the handler it already has.
+#[derive(serde::Serialize)]
+pub struct ShortcutInfo {
+    pub key_combo: &'static str,
+    pub description: &'static str,
+    pub command: &'static str,
+}
+
+/// Single source of truth for the shortcut help overlay. Anything that
+/// registers a shortcut should add it here so the overlay stays accurate.
+pub const KEYBOARD_SHORTCUTS: &[ShortcutInfo] = &[
+    ShortcutInfo {
+        key_combo: "CmdOrCtrl+S",
+        description: "Save the current file",
+        command: "save_file",
+    },
+    ShortcutInfo {
+        key_combo: "CmdOrCtrl+Enter",
+        description: "Commit staged changes",
+        command: "commit_changes",
+    },
+    ShortcutInfo {
+        key_combo: "ArrowLeft / ArrowRight",
+        description: "Move focus to the previous / next deck tab",
+        command: "focus_adjacent_deck_tab",
+    },
+    ShortcutInfo {
+        key_combo: "Home / End",
+        description: "Move focus to the first / last deck tab",
+        command: "focus_edge_deck_tab",
+    },
+    ShortcutInfo {
+        key_combo: "Escape",
+        description: "Close the open dialog",
+        command: "close_modal",
+    },
+];
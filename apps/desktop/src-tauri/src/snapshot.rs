@@ -0,0 +1,216 @@
+use crate::commands::{ServerStatus, TunnelStatus};
+use crate::error::AppError;
+use crate::metrics::{self, ServerMetrics};
+use crate::server::{self, CircuitState, HealthCheck, LogLine, PackageManager, RestartEvent};
+use crate::{diagnostics, AppState};
+use chrono::Local;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{Emitter, Manager};
+
+/// Log lines kept in a snapshot, newest last
+const SNAPSHOT_LOG_LINES: usize = 500;
+const SNAPSHOT_DIR: &str = "snapshots";
+
+/// Everything `create` records about the server at one moment.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    created_at: String,
+    server: ServerStatus,
+    /// `None` when no server is running
+    config: Option<SnapshotConfig>,
+    circuit: CircuitState,
+    resources: Option<ResourceUsage>,
+    metrics: ServerMetrics,
+    restart_history: Vec<RestartEvent>,
+    tunnel: TunnelStatus,
+    logs: Vec<LogLine>,
+}
+
+/// The parts of the running server's `ServerConfig` worth seeing when
+/// debugging. Secret-looking env values are redacted along with the rest
+/// of the file.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotConfig {
+    port: u16,
+    working_dir: Option<PathBuf>,
+    node_binary: Option<PathBuf>,
+    script: Option<PathBuf>,
+    package_manager: Option<PackageManager>,
+    extra_args: Vec<String>,
+    env: HashMap<String, String>,
+    max_memory_mb: Option<u64>,
+    health_check: HealthCheck,
+    health_poll_interval_secs: Option<u64>,
+    max_restarts: u32,
+}
+
+impl SnapshotConfig {
+    fn of(config: &server::ServerConfig) -> Self {
+        SnapshotConfig {
+            port: config.port,
+            working_dir: config.working_dir.clone(),
+            node_binary: config.node_binary.clone(),
+            script: config.script.clone(),
+            package_manager: config.package_manager,
+            extra_args: config.extra_args.clone(),
+            env: config.env.clone(),
+            max_memory_mb: config.max_memory_mb,
+            health_check: config.health_check.clone(),
+            health_poll_interval_secs: config.health_poll_interval.map(|i| i.as_secs()),
+            max_restarts: config.max_restarts,
+        }
+    }
+}
+
+/// CPU and memory of the server process and everything it spawned.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceUsage {
+    cpu_percent: f32,
+    memory_bytes: u64,
+    processes: usize,
+}
+
+/// A saved snapshot, from `list`.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    /// Pass to `delete_snapshot`
+    pub id: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// Record the server's state as `app_data_dir()/snapshots/<timestamp>.json`,
+/// emit `snapshot-created` and return the file's path. Status, config,
+/// logs and history are read under the server and tunnel locks so they
+/// agree with each other; metrics and CPU usage are measured after.
+pub async fn create(state: &AppState) -> Result<PathBuf, AppError> {
+    let app = &state.app_handle;
+    let (mut snapshot, port, metrics_path, pid) = {
+        let server_state = state.server.lock().await;
+        let tunnel_state = state.tunnel.lock().await;
+        let handle = server_state.as_ref();
+        let snapshot = Snapshot {
+            created_at: Local::now().to_rfc3339(),
+            server: ServerStatus::of(handle, &state.server_history),
+            config: handle.map(|h| SnapshotConfig::of(h.config())),
+            circuit: state.server_circuit.state(),
+            resources: None,
+            metrics: ServerMetrics::default(),
+            restart_history: state.server_history.events(),
+            tunnel: TunnelStatus::of(tunnel_state.as_ref(), &state.tunnel_history).await,
+            logs: state.server_logs.recent(SNAPSHOT_LOG_LINES),
+        };
+        let metrics_path = handle.and_then(|h| h.config().metrics_path.clone());
+        (snapshot, handle.map(|h| h.port), metrics_path, handle.and_then(|h| h.pid()))
+    };
+    if let Some(port) = port {
+        let path = metrics_path.unwrap_or_else(|| metrics::DEFAULT_METRICS_PATH.to_string());
+        snapshot.metrics = metrics::fetch(port, &path).await;
+    }
+    if let Some(pid) = pid {
+        snapshot.resources = Some(resource_usage(pid).await);
+    }
+
+    let json = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| format!("Failed to encode snapshot: {}", e))?;
+    let dir = snapshot_dir(app)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.json", Local::now().format("%Y%m%d_%H%M%S_%3f")));
+    std::fs::write(&path, diagnostics::redact_sensitive(&json))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    tracing::info!("Saved server snapshot to {}", path.display());
+    let _ = app.emit("snapshot-created", serde_json::json!({ "path": path }));
+    Ok(path)
+}
+
+/// Saved snapshots, newest first.
+pub fn list(app: &tauri::AppHandle) -> Result<Vec<SnapshotInfo>, AppError> {
+    let dir = snapshot_dir(app)?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e).into()),
+    };
+
+    let mut snapshots: Vec<SnapshotInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let id = path.file_stem()?.to_str()?.to_string();
+            if path.extension()? != "json" || !is_valid_id(&id) {
+                return None;
+            }
+            let size_bytes = entry.metadata().ok()?.len();
+            Some(SnapshotInfo { id, path, size_bytes })
+        })
+        .collect();
+    // Ids are timestamps, so they sort by age
+    snapshots.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(snapshots)
+}
+
+pub fn delete(app: &tauri::AppHandle, id: &str) -> Result<(), AppError> {
+    // Ids come from the frontend; keep them from naming other files
+    if !is_valid_id(id) {
+        return Err(AppError::InvalidArgument(format!("Invalid snapshot id '{}'", id)));
+    }
+    let path = snapshot_dir(app)?.join(format!("{}.json", id));
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    tracing::info!("Deleted server snapshot {}", id);
+    Ok(())
+}
+
+fn snapshot_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    Ok(dir.join(SNAPSHOT_DIR))
+}
+
+/// `<date>_<time>_<millis>`, as `create` names them.
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_digit() || c == '_')
+}
+
+/// Total CPU and memory of `pid` and its descendants. CPU needs two
+/// samples, so this takes a moment.
+async fn resource_usage(pid: u32) -> ResourceUsage {
+    use sysinfo::{Pid, System};
+
+    let mut system = System::new();
+    system.refresh_processes();
+    tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
+    system.refresh_processes();
+    let processes = system.processes();
+
+    let mut tree = vec![Pid::from_u32(pid)];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        tree.extend(processes.iter().filter(|(_, p)| p.parent() == Some(parent)).map(|(pid, _)| *pid));
+        i += 1;
+    }
+    let found: Vec<_> = tree.iter().filter_map(|pid| processes.get(pid)).collect();
+    ResourceUsage {
+        cpu_percent: found.iter().map(|p| p.cpu_usage()).sum(),
+        memory_bytes: found.iter().map(|p| p.memory()).sum(),
+        processes: found.len(),
+    }
+}
+
+/// The `limit` newest snapshot files, for the diagnostics bundle.
+pub fn newest_files(app: &tauri::AppHandle, limit: usize) -> Result<Vec<(String, PathBuf)>, AppError> {
+    Ok(list(app)?
+        .into_iter()
+        .take(limit)
+        .map(|info| (format!("{}.json", info.id), info.path))
+        .collect())
+}
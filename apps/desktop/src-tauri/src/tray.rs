@@ -0,0 +1,185 @@
+use crate::{commands, window, AppState};
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::Manager;
+use std::sync::Mutex;
+
+const TRAY_ID: &str = "main";
+
+/// Tray icons are generated rather than bundled: a filled circle this many
+/// pixels across
+const ICON_SIZE: u32 = 32;
+
+const GREY: [u8; 3] = [0x8a, 0x8a, 0x8a];
+const YELLOW: [u8; 3] = [0xe8, 0xb3, 0x1a];
+const GREEN: [u8; 3] = [0x2e, 0xb8, 0x5c];
+const BLUE: [u8; 3] = [0x2f, 0x80, 0xed];
+/// A tunnel with no server behind it
+const ORANGE: [u8; 3] = [0xe8, 0x6a, 0x1a];
+
+/// Lifecycle of the server or the tunnel as the tray shows it. Running and
+/// stopped follow `events::emit_*_changed`; starting is set directly, as
+/// the state events have no equivalent.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum ServiceState {
+    #[default]
+    Stopped,
+    Starting,
+    Running,
+}
+
+pub fn set_server_state(app: &tauri::AppHandle, state: ServiceState) {
+    update(app, |tray| {
+        tray.status.server = state;
+        let _ = tray.server_item.set_text(toggle_label("Server", state));
+    });
+}
+
+pub fn set_tunnel_state(app: &tauri::AppHandle, state: ServiceState) {
+    update(app, |tray| {
+        tray.status.tunnel = state;
+        let _ = tray.tunnel_item.set_text(toggle_label("Tunnel", state));
+    });
+}
+
+/// The tray's status and the menu items that reflect it, managed once the
+/// tray exists
+struct TrayMenu {
+    status: TrayStatus,
+    server_item: MenuItem<tauri::Wry>,
+    tunnel_item: MenuItem<tauri::Wry>,
+}
+
+fn update(app: &tauri::AppHandle, change: impl FnOnce(&mut TrayMenu)) {
+    let Some(menu) = app.try_state::<Mutex<TrayMenu>>() else {
+        return;
+    };
+    let mut menu = menu.lock().unwrap_or_else(|e| e.into_inner());
+    change(&mut menu);
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_icon(Some(status_icon(menu.status.color())));
+        let _ = tray.set_tooltip(Some(menu.status.tooltip()));
+    }
+}
+
+#[derive(Default)]
+struct TrayStatus {
+    server: ServiceState,
+    tunnel: ServiceState,
+}
+
+impl TrayStatus {
+    fn color(&self) -> [u8; 3] {
+        use ServiceState::*;
+        match (self.server, self.tunnel) {
+            (Running, Running) => BLUE,
+            (Starting, _) | (_, Starting) => YELLOW,
+            (Running, _) => GREEN,
+            (_, Running) => ORANGE,
+            _ => GREY,
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        let describe = |state| match state {
+            ServiceState::Stopped => "stopped",
+            ServiceState::Starting => "starting",
+            ServiceState::Running => "running",
+        };
+        format!(
+            "S-IDE - server {}, tunnel {}",
+            describe(self.server),
+            describe(self.tunnel)
+        )
+    }
+}
+
+/// Create the tray icon, which `set_server_state` and `set_tunnel_state`
+/// keep up to date.
+pub fn setup(app: &tauri::App) -> tauri::Result<()> {
+    let show = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let server = MenuItem::with_id(app, "server", "Server: Start", true, None::<&str>)?;
+    let tunnel = MenuItem::with_id(app, "tunnel", "Tunnel: Start", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&show, &server, &tunnel, &quit])?;
+
+    let status = TrayStatus::default();
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(status_icon(status.color()))
+        .tooltip(status.tooltip())
+        .menu(&menu)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "show" => window::show_main_window(app),
+            "server" => toggle_server(app.clone()),
+            "tunnel" => toggle_tunnel(app.clone()),
+            "quit" => {
+                window::save_main_window_state(app);
+                app.exit(0);
+            }
+            _ => {}
+        })
+        .build(app)?;
+
+    app.manage(Mutex::new(TrayMenu { status, server_item: server, tunnel_item: tunnel }));
+    Ok(())
+}
+
+fn toggle_label(service: &str, state: ServiceState) -> String {
+    match state {
+        ServiceState::Stopped => format!("{}: Start", service),
+        ServiceState::Starting | ServiceState::Running => format!("{}: Stop", service),
+    }
+}
+
+fn toggle_server(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let running = app.state::<AppState>().server.lock().await.is_some();
+        let result = if running {
+            commands::stop_server(app.state()).await
+        } else {
+            commands::start_server(app.state(), None, None).await
+        };
+        if let Err(e) = result {
+            tracing::error!("Tray server toggle failed: {}", e);
+        }
+    });
+}
+
+fn toggle_tunnel(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let running = state.tunnel.lock().await.is_some();
+        let result = if running {
+            commands::stop_tunnel(app.state()).await
+        } else {
+            let port = state.server
+                .lock()
+                .await
+                .as_ref()
+                .map(|h| h.port)
+                .unwrap_or(window::DEFAULT_SERVER_PORT);
+            commands::start_tunnel(app.state(), port, None, None, None).await
+        };
+        if let Err(e) = result {
+            tracing::error!("Tray tunnel toggle failed: {}", e);
+        }
+    });
+}
+
+/// A filled circle in `color` with an anti-aliased edge.
+fn status_icon(color: [u8; 3]) -> Image<'static> {
+    let center = ICON_SIZE as f32 / 2.0;
+    let radius = center - 2.0;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let dx = x as f32 + 0.5 - center;
+            let dy = y as f32 + 0.5 - center;
+            let coverage = (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0);
+            rgba.extend_from_slice(&color);
+            rgba.push((coverage * 255.0) as u8);
+        }
+    }
+    Image::new_owned(rgba, ICON_SIZE, ICON_SIZE)
+}
@@ -1,43 +1,626 @@
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Child;
-use tokio::sync::Mutex;
+use tokio::sync::{oneshot, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::commands::TunnelStatus;
+use crate::error::AppError;
+use crate::events;
+use crate::keychain;
+use crate::notification;
+use crate::process::{ProcessGroup, ProcessLimits};
+use crate::server;
+use crate::tray::{self, ServiceState};
+use crate::AppState;
+use tauri::{Emitter, Manager};
+
+/// How often the reconnect watcher checks whether the process has exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
+/// A tunnel that stayed up this long before dropping gets a fresh
+/// reconnect budget
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+/// How long a provider with a `fixed_url` gets to fail before it is
+/// considered connected
+const FIXED_URL_GRACE: Duration = Duration::from_secs(3);
+
+/// Source of `TunnelHandle::id`, used to tell handles apart across reconnects
+static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Service used to expose the local server publicly.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelProvider {
+    /// `npx localtunnel`
+    #[default]
+    LocalTunnel,
+    /// Cloudflare quick tunnels (`cloudflared tunnel --url`)
+    Cloudflared,
+    /// Any command that prints its public URL; `{port}`, `{subdomain}` and
+    /// `{token}` in `args` are replaced with the local port, requested
+    /// subdomain and auth token
+    Custom { command: String, args: Vec<String> },
+    /// A reverse tunnel to your own SSH server, which must allow remote
+    /// forwards on a public interface (`GatewayPorts yes`)
+    Ssh {
+        host: String,
+        port: u16,
+        user: String,
+        remote_port: u16,
+        identity_file: Option<PathBuf>,
+    },
+}
+
+impl TunnelProvider {
+    fn command(&self, config: &TunnelConfig) -> tokio::process::Command {
+        let port = config.port;
+        match self {
+            TunnelProvider::LocalTunnel => {
+                // npx is a .cmd shim on Windows, which a bare name doesn't
+                // find; if PATH has none, spawning reports it
+                let npx = which::which("npx").unwrap_or_else(|_| "npx".into());
+                let mut command = tokio::process::Command::new(npx);
+                // --print-requests logs each request, which get_tunnel_stats counts
+                command
+                    .arg("localtunnel")
+                    .arg("--port")
+                    .arg(port.to_string())
+                    .arg("--print-requests");
+                if let Some(subdomain) = &config.subdomain {
+                    command.arg("--subdomain").arg(subdomain);
+                }
+                command
+            }
+            TunnelProvider::Cloudflared => {
+                let mut command = tokio::process::Command::new("cloudflared");
+                command.arg("tunnel").arg("--url").arg(format!("http://localhost:{}", port));
+                // Only works with a logged-in cloudflared; quick tunnels
+                // always get a random trycloudflare.com name
+                if let Some(hostname) = &config.subdomain {
+                    command.arg("--hostname").arg(hostname);
+                }
+                // Read by cloudflared instead of `--token`, which would show
+                // up in process listings
+                if let Some(token) = &config.auth_token {
+                    command.env("TUNNEL_TOKEN", token);
+                }
+                command
+            }
+            TunnelProvider::Custom { command: program, args } => {
+                let subdomain = config.subdomain.as_deref().unwrap_or_default();
+                let token = config.auth_token.as_deref().unwrap_or_default();
+                let mut command = tokio::process::Command::new(program);
+                command.args(args.iter().map(|arg| {
+                    arg.replace("{port}", &port.to_string())
+                        .replace("{subdomain}", subdomain)
+                        .replace("{token}", token)
+                }));
+                command
+            }
+            TunnelProvider::Ssh { host, port: ssh_port, user, remote_port, identity_file } => {
+                let mut command = tokio::process::Command::new("ssh");
+                command
+                    .arg("-N")
+                    .arg("-R")
+                    .arg(format!("{}:localhost:{}", remote_port, port))
+                    .arg("-p")
+                    .arg(ssh_port.to_string())
+                    // Exit (and let the reconnect watcher retry) rather than
+                    // stay connected without the forward
+                    .args(["-o", "ExitOnForwardFailure=yes"])
+                    // There is no terminal to ask for a password or key
+                    // passphrase on, so fail instead of hanging. Encrypted
+                    // keys need ssh-agent; prompting would mean pointing
+                    // SSH_ASKPASS at a helper, which isn't supported here.
+                    .args(["-o", "BatchMode=yes"])
+                    .args(["-o", "ServerAliveInterval=30"]);
+                if let Some(identity) = identity_file {
+                    command.arg("-i").arg(identity);
+                }
+                command.arg(format!("{}@{}", user, host));
+                command
+            }
+        }
+    }
+
+    /// The public URL for providers that don't print one: known from the
+    /// config alone.
+    fn fixed_url(&self) -> Option<String> {
+        match self {
+            TunnelProvider::Ssh { host, remote_port, .. } => Some(format!("http://{}:{}", host, remote_port)),
+            _ => None,
+        }
+    }
+
+    fn parse_url(&self, line: &str, subdomain: Option<&str>) -> Option<String> {
+        match self {
+            TunnelProvider::LocalTunnel => parse_localtunnel_url(line),
+            TunnelProvider::Cloudflared => find_url(line, |url| {
+                let host = url_host(url);
+                url.starts_with("https://")
+                    && (host.ends_with(".trycloudflare.com") || Some(host) == subdomain)
+            }),
+            TunnelProvider::Custom { .. } => find_url(line, |_| true),
+            // `ssh -N` prints nothing; see `fixed_url`
+            TunnelProvider::Ssh { .. } => None,
+        }
+    }
+
+    /// Whether `line` is the provider logging one proxied request. Only
+    /// localtunnel does that.
+    fn is_request_line(&self, line: &str) -> bool {
+        match self {
+            TunnelProvider::LocalTunnel => is_localtunnel_request(line),
+            TunnelProvider::Cloudflared | TunnelProvider::Custom { .. } | TunnelProvider::Ssh { .. } => false,
+        }
+    }
+
+    /// Whether `auth_token` means anything to this provider.
+    fn uses_token(&self) -> bool {
+        matches!(self, TunnelProvider::Cloudflared | TunnelProvider::Custom { .. })
+    }
+
+    /// Also the keychain account its token is saved under.
+    pub fn name(&self) -> &str {
+        match self {
+            TunnelProvider::LocalTunnel => "localtunnel",
+            TunnelProvider::Cloudflared => "cloudflared",
+            TunnelProvider::Custom { command, .. } => command,
+            TunnelProvider::Ssh { .. } => "ssh",
+        }
+    }
+}
+
+/// Providers whose binaries are on PATH. `Custom` and `Ssh`, which need
+/// settings of their own, are never reported.
+pub fn detect_available_providers() -> Vec<TunnelProvider> {
+    let mut providers = Vec::new();
+    if which::which("npx").is_ok() {
+        providers.push(TunnelProvider::LocalTunnel);
+    }
+    if which::which("cloudflared").is_ok() {
+        providers.push(TunnelProvider::Cloudflared);
+    }
+    providers
+}
+
+/// Parameters for starting (and automatically reconnecting) a tunnel.
+#[derive(Clone)]
+pub struct TunnelConfig {
+    /// Local port to expose
+    pub port: u16,
+    pub provider: TunnelProvider,
+    /// Requested subdomain (a full hostname for cloudflared); providers may
+    /// assign a different one if it is taken
+    pub subdomain: Option<String>,
+    /// Passed to cloudflared as `TUNNEL_TOKEN` and to custom commands as
+    /// `{token}`; localtunnel has no accounts and ignores it. When unset,
+    /// `start` uses the one saved with `secure_store_tunnel_token`.
+    pub auth_token: Option<String>,
+    /// Reconnect attempts after the process exits before giving up
+    pub max_reconnect_attempts: u32,
+    /// How long to wait for the provider to print its public URL
+    pub startup_timeout: Duration,
+    /// Check for an internet connection before starting, so a tunnel
+    /// started offline fails at once instead of timing out
+    pub preflight_check: bool,
+    /// Wait for the server on `port` to pass its health check before
+    /// starting, instead of tunnelling to a port nothing answers on
+    pub wait_for_server: bool,
+    /// How long `wait_for_server` waits before giving up
+    pub server_wait_timeout: Duration,
+    /// What `wait_for_server` checks for
+    pub server_health_check: server::HealthCheck,
+}
+
+impl Default for TunnelConfig {
+    fn default() -> Self {
+        TunnelConfig {
+            port: crate::window::DEFAULT_SERVER_PORT,
+            provider: TunnelProvider::default(),
+            subdomain: None,
+            auth_token: None,
+            max_reconnect_attempts: 10,
+            startup_timeout: Duration::from_secs(15),
+            preflight_check: true,
+            wait_for_server: false,
+            server_wait_timeout: Duration::from_secs(30),
+            server_health_check: server::HealthCheck::default(),
+        }
+    }
+}
+
+/// Tunnel settings as sent by the frontend. Every field is optional and
+/// falls back to the config it is merged onto.
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelConfigRequest {
+    pub port: Option<u16>,
+    pub provider: Option<TunnelProvider>,
+    pub subdomain: Option<String>,
+    pub auth_token: Option<String>,
+    pub max_reconnects: Option<u32>,
+    pub startup_timeout_secs: Option<u64>,
+    pub preflight_check: Option<bool>,
+    pub wait_for_server: Option<bool>,
+    pub server_wait_timeout_secs: Option<u64>,
+    /// Shorthand for `provider: { custom: { command, args } }`
+    pub custom_command: Option<(String, Vec<String>)>,
+}
+
+impl TunnelConfigRequest {
+    /// Overlay the fields that were set onto `config`.
+    pub fn merge_into(self, mut config: TunnelConfig) -> TunnelConfig {
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        if let Some(provider) = self.provider {
+            config.provider = provider;
+        }
+        if let Some((command, args)) = self.custom_command {
+            config.provider = TunnelProvider::Custom { command, args };
+        }
+        if let Some(subdomain) = self.subdomain {
+            config.subdomain = Some(subdomain);
+        }
+        if let Some(token) = self.auth_token {
+            config.auth_token = Some(token);
+        }
+        if let Some(attempts) = self.max_reconnects {
+            config.max_reconnect_attempts = attempts;
+        }
+        if let Some(secs) = self.startup_timeout_secs {
+            config.startup_timeout = Duration::from_secs(secs);
+        }
+        if let Some(enabled) = self.preflight_check {
+            config.preflight_check = enabled;
+        }
+        if let Some(enabled) = self.wait_for_server {
+            config.wait_for_server = enabled;
+        }
+        if let Some(secs) = self.server_wait_timeout_secs {
+            config.server_wait_timeout = Duration::from_secs(secs);
+        }
+        config
+    }
+}
+
+/// Delay between health checks while `wait_for_server` waits
+const SERVER_WAIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Poll the server's health endpoint on `port` until it passes `check`, for
+/// up to `timeout`.
+async fn wait_for_server(port: u16, check: &server::HealthCheck, timeout: Duration) -> Result<(), AppError> {
+    tracing::info!("Waiting up to {}s for the server on port {}", timeout.as_secs(), port);
+    // The timeout, not the retry count, is what bounds the wait
+    let probe = server::probe_server_health(port, check, u8::MAX, SERVER_WAIT_INTERVAL);
+    match tokio::time::timeout(timeout, probe).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(_)) | Err(_) => Err(AppError::ServerNotReady { port, after: timeout }),
+    }
+}
+
+/// Fetched by `network_check` to tell whether the internet is reachable
+pub const CONNECTIVITY_CHECK_URL: &str = "https://cloudflare.com";
+/// Limit for each half of `network_check`
+const NETWORK_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(serde::Serialize)]
+pub struct NetworkStatus {
+    pub internet_available: bool,
+    pub local_server_reachable: bool,
+    /// Round trip of the connectivity check, when it succeeded
+    pub latency_ms: Option<u64>,
+}
+
+/// Whether a tunnel would work right now: the internet is reachable and
+/// something is listening on local `port`. Both checks run at once.
+pub async fn network_check(port: u16) -> NetworkStatus {
+    let (latency, local_server_reachable) = tokio::join!(internet_latency(), local_port_open(port));
+    NetworkStatus {
+        internet_available: latency.is_some(),
+        local_server_reachable,
+        latency_ms: latency.map(|l| l.as_millis() as u64),
+    }
+}
+
+/// Time for a `HEAD` of `CONNECTIVITY_CHECK_URL`. Any HTTP response counts;
+/// only a failure to get one means there is no connection.
+async fn internet_latency() -> Option<Duration> {
+    let client = reqwest::Client::builder().timeout(NETWORK_CHECK_TIMEOUT).build().ok()?;
+    let started = Instant::now();
+    client.head(CONNECTIVITY_CHECK_URL).send().await.ok()?;
+    Some(started.elapsed())
+}
+
+async fn local_port_open(port: u16) -> bool {
+    tokio::time::timeout(NETWORK_CHECK_TIMEOUT, tokio::net::TcpStream::connect(("localhost", port)))
+        .await
+        .is_ok_and(|connected| connected.is_ok())
+}
 
 pub struct TunnelHandle {
-    _child: Child,
+    // Shared with the reconnect watcher, which polls it with try_wait
+    child: Arc<Mutex<Child>>,
+    id: u64,
+    config: TunnelConfig,
+    started_at: Instant,
+    /// Set by `stop` so the reconnect watcher ignores the resulting exit
+    stopping: Arc<AtomicBool>,
     url: Arc<Mutex<Option<String>>>,
+    /// Requests the provider has reported proxying
+    request_count: Arc<AtomicU64>,
+    group: Option<ProcessGroup>,
 }
 
-pub async fn start(port: u16) -> Result<TunnelHandle, String> {
-    // For now, return a simple handle without URL monitoring
-    // Full implementation would need output monitoring which is complex in async Rust
-    let child = tokio::process::Command::new("npx")
-        .arg("localtunnel")
-        .arg("--port")
-        .arg(port.to_string())
+impl TunnelHandle {
+    /// Time since this tunnel process was spawned.
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Requests proxied by this tunnel process, as far as the provider
+    /// reports them; always 0 for providers that don't.
+    pub fn request_count(&self) -> u64 {
+        self.request_count.load(Ordering::Relaxed)
+    }
+}
+
+pub async fn start(mut config: TunnelConfig) -> Result<TunnelHandle, AppError> {
+    if config.wait_for_server {
+        wait_for_server(config.port, &config.server_health_check, config.server_wait_timeout).await?;
+    }
+    if config.auth_token.is_none() && config.provider.uses_token() {
+        // A broken keychain shouldn't stop a provider that may not need it
+        match keychain::get_secret(config.provider.name()).await {
+            Ok(token) => config.auth_token = token,
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+
+    let provider = config.provider.clone();
+    let mut command = provider.command(&config);
+    command
         .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    // npx runs the actual tunnel client as a grandchild
+    ProcessGroup::prepare(&mut command);
+    let mut child = command
         .spawn()
-        .map_err(|e| format!("Failed to start tunnel: {}. Ensure Node.js/npm is in PATH", e))?;
+        .inspect_err(|e| tracing::error!("Failed to start {}: {}. Ensure it is in PATH", provider.name(), e))?;
+    // Attached before waiting for the URL so a client that forks early is
+    // still caught
+    let group = ProcessGroup::attach(&child, &ProcessLimits::default())
+        .inspect_err(|e| tracing::warn!("Tunnel subprocesses won't be stopped with it: {}", e))
+        .ok();
+    let kill_group = || group.iter().for_each(ProcessGroup::kill);
+
+    let stdout = child.stdout.take().ok_or("Failed to capture tunnel output")?;
+    let stderr = child.stderr.take().ok_or("Failed to capture tunnel output")?;
 
-    // Start a background task to monitor output
     let url = Arc::new(Mutex::new(None));
-    let _url_clone = Arc::clone(&url);
+    let request_count = Arc::new(AtomicU64::new(0));
+    let (first_url_tx, first_url_rx) = oneshot::channel();
+    // Shared by both readers since cloudflared logs its URL to stderr; the
+    // receiver sees an error once both have finished without a URL
+    let first_url_tx = Arc::new(std::sync::Mutex::new(Some(first_url_tx)));
+
+    let output = OutputWatch {
+        provider,
+        subdomain: config.subdomain.clone(),
+        url: Arc::clone(&url),
+        first_url_tx,
+        request_count: Arc::clone(&request_count),
+    };
+    tokio::spawn(watch_output(stdout, false, output.clone()));
+    tokio::spawn(watch_output(stderr, true, output));
 
-    // Note: We can't easily read stdout after spawn in current design
-    // For now, users will see the URL in their terminal or we can add proper logging later
+    if let Some(fixed) = config.provider.fixed_url() {
+        *url.lock().await = Some(fixed);
+        // Nothing will be printed, but a rejected key or refused forward
+        // makes ssh exit (closing both readers) within moments
+        if let Ok(Err(_)) = tokio::time::timeout(FIXED_URL_GRACE, first_url_rx).await {
+            kill_group();
+            return Err(format!("{} exited while connecting; see the log for why", config.provider.name()).into());
+        }
+    } else {
+        match tokio::time::timeout(config.startup_timeout, first_url_rx).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(_)) => {
+                kill_group();
+                return Err("Tunnel process exited before reporting a URL".into());
+            }
+            // Still running; the URL may show up later and get_url will pick it up
+            Err(_) => tracing::warn!("No URL reported within {}s", config.startup_timeout.as_secs()),
+        }
+    }
+
+    Ok(TunnelHandle {
+        group,
+        child: Arc::new(Mutex::new(child)),
+        id: NEXT_HANDLE_ID.fetch_add(1, Ordering::Relaxed),
+        config,
+        started_at: Instant::now(),
+        stopping: Arc::new(AtomicBool::new(false)),
+        url,
+        request_count,
+    })
+}
+
+/// Watch the tunnel process in the background and start it again with
+/// exponential back-off if it exits, replacing the handle in `AppState`.
+///
+/// Emits `tunnel-disconnected` on every drop and `tunnel-failed` once
+/// `max_reconnect_attempts` attempts in a row have failed, at which point
+/// the tunnel state is cleared.
+pub fn spawn_reconnect_watcher(app: tauri::AppHandle, handle: &TunnelHandle) {
+    spawn_reconnect_watcher_with_count(app, handle, 0);
+}
 
-    tokio::spawn(async move {
-        // Give tunnel a moment to start and log its URL
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-        // In production, you'd read stdout here and parse the URL
-        // For now, user can check the terminal where npx is running
+fn spawn_reconnect_watcher_with_count(app: tauri::AppHandle, handle: &TunnelHandle, reconnects: u32) {
+    let child = Arc::clone(&handle.child);
+    let stopping = Arc::clone(&handle.stopping);
+    let url = Arc::clone(&handle.url);
+    let id = handle.id;
+    let config = handle.config.clone();
+    let started_at = handle.started_at;
+
+    tauri::async_runtime::spawn(async move {
+        let status = loop {
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+            match child.lock().await.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::error!("Lost track of tunnel process: {}", e);
+                    return;
+                }
+            }
+        };
+
+        if stopping.load(Ordering::SeqCst) {
+            return;
+        }
+
+        // The output readers clear this on EOF too, but don't leave a stale
+        // URL around if they haven't caught up yet
+        *url.lock().await = None;
+        tracing::error!("Tunnel process exited: {}", status);
+        let _ = app.emit(
+            "tunnel-disconnected",
+            serde_json::json!({ "exit_code": status.code() }),
+        );
+
+        let state = app.state::<AppState>();
+        let mut attempts = if started_at.elapsed() >= STABLE_UPTIME { 0 } else { reconnects };
+        if attempts < config.max_reconnect_attempts {
+            tray::set_tunnel_state(&app, ServiceState::Starting);
+        }
+        while attempts < config.max_reconnect_attempts {
+            let backoff = Duration::from_secs(2u64.saturating_pow(attempts)).min(MAX_RECONNECT_BACKOFF);
+            tokio::time::sleep(backoff).await;
+            attempts += 1;
+
+            // Stopped or replaced by a command while we were waiting
+            if state.tunnel.lock().await.as_ref().map(|h| h.id) != Some(id) {
+                return;
+            }
+
+            tracing::info!(
+                "Reconnecting (attempt {}/{})",
+                attempts, config.max_reconnect_attempts
+            );
+            // Not under the state lock, which would block every tunnel
+            // command until the new URL shows up
+            match start(config.clone()).await {
+                Ok(new_handle) => {
+                    let mut tunnel_state = state.tunnel.lock().await;
+                    // Stopped or replaced while this one was connecting
+                    if tunnel_state.as_ref().map(|h| h.id) != Some(id) {
+                        drop(tunnel_state);
+                        if let Err(e) = stop(new_handle).await {
+                            tracing::warn!("Failed to stop superseded reconnect: {}", e);
+                        }
+                        return;
+                    }
+                    state.tunnel_history.record(format!("Disconnected ({})", status));
+                    if let Some(url) = get_url(&new_handle).await {
+                        check_subdomain(&app, &new_handle, &url);
+                        notification::show(&app, "Tunnel reconnected", url.clone());
+                    }
+                    spawn_reconnect_watcher_with_count(app.clone(), &new_handle, attempts);
+                    *tunnel_state = Some(new_handle);
+                    let status = TunnelStatus::of(tunnel_state.as_ref(), &state.tunnel_history).await;
+                    events::emit_tunnel_changed(&app, &status);
+                    return;
+                }
+                Err(e) => tracing::error!("Reconnect failed: {}", e),
+            }
+        }
+
+        let mut tunnel_state = state.tunnel.lock().await;
+        if tunnel_state.as_ref().map(|h| h.id) == Some(id) {
+            *tunnel_state = None;
+            state.share_links.lock().await.clear();
+            events::emit_tunnel_changed(&app, &TunnelStatus::of(None, &state.tunnel_history).await);
+        }
+        drop(tunnel_state);
+        let _ = app.emit(
+            "tunnel-failed",
+            serde_json::json!({ "attempts": attempts }),
+        );
     });
+}
 
-    Ok(TunnelHandle { _child: child, url })
+#[derive(Clone)]
+struct OutputWatch {
+    provider: TunnelProvider,
+    subdomain: Option<String>,
+    url: Arc<Mutex<Option<String>>>,
+    first_url_tx: Arc<std::sync::Mutex<Option<oneshot::Sender<String>>>>,
+    request_count: Arc<AtomicU64>,
+}
+
+/// Watch one output stream for the URL for as long as the tunnel runs. When
+/// the stream closes the process has exited, so the URL is no longer valid.
+async fn watch_output<R: AsyncRead + Unpin>(stream: R, is_stderr: bool, watch: OutputWatch) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if is_stderr {
+            tracing::info!(target: "tunnel", "{}", line);
+        }
+        if watch.provider.is_request_line(&line) {
+            watch.request_count.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(found) = watch.provider.parse_url(&line, watch.subdomain.as_deref()) {
+            *watch.url.lock().await = Some(found.clone());
+            let tx = watch.first_url_tx.lock().unwrap_or_else(|e| e.into_inner()).take();
+            if let Some(tx) = tx {
+                let _ = tx.send(found);
+            }
+        }
+    }
+    *watch.url.lock().await = None;
+}
+
+/// The first DNS label of the URL's host, e.g. `brave-fox` for
+/// `https://brave-fox.loca.lt`.
+pub fn subdomain_of(url: &str) -> Option<String> {
+    let host = url_host(url);
+    host.split_once('.').map(|(label, _)| label.to_string())
 }
 
-pub async fn stop(mut handle: TunnelHandle) -> Result<(), String> {
-    handle._child.kill()
+/// Emit `tunnel-subdomain-conflict` if the provider gave `url` instead of
+/// the subdomain the handle asked for.
+pub fn check_subdomain(app: &tauri::AppHandle, handle: &TunnelHandle, url: &str) {
+    let Some(requested) = &handle.config.subdomain else {
+        return;
+    };
+    let host = url_host(url);
+    // cloudflared takes a full hostname, localtunnel just the first label
+    let matches = host == requested || subdomain_of(url).as_deref() == Some(requested.as_str());
+    if !matches {
+        tracing::warn!("Requested subdomain '{}' but got {}", requested, url);
+        let _ = app.emit(
+            "tunnel-subdomain-conflict",
+            serde_json::json!({ "requested": requested, "url": url }),
+        );
+    }
+}
+
+pub async fn stop(handle: TunnelHandle) -> Result<(), AppError> {
+    handle.stopping.store(true, Ordering::SeqCst);
+    if let Some(group) = &handle.group {
+        group.kill();
+    }
+    let mut child = handle.child.lock().await;
+    // Already exited and reaped by the reconnect watcher
+    if let Ok(Some(_)) = child.try_wait() {
+        return Ok(());
+    }
+    child.kill()
         .await
         .map_err(|e| format!("Failed to stop tunnel: {}", e))?;
     Ok(())
@@ -46,3 +629,37 @@ pub async fn stop(mut handle: TunnelHandle) -> Result<(), String> {
 pub async fn get_url(handle: &TunnelHandle) -> Option<String> {
     handle.url.lock().await.clone()
 }
+
+/// Extract the public URL from localtunnel's `your url is: https://...` line.
+fn parse_localtunnel_url(line: &str) -> Option<String> {
+    const PREFIX: &str = "your url is:";
+
+    let start = line.to_ascii_lowercase().find(PREFIX)? + PREFIX.len();
+    let url = line[start..].trim();
+    (url.starts_with("https://") || url.starts_with("http://")).then(|| url.to_string())
+}
+
+/// localtunnel's `--print-requests` lines are a date followed by the
+/// method and path: `Wed Oct 14 2026 10:00:00 GMT+0000 (...) GET /api`.
+fn is_localtunnel_request(line: &str) -> bool {
+    const METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS", "CONNECT", "TRACE"];
+
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    tokens
+        .windows(2)
+        .any(|pair| METHODS.contains(&pair[0]) && pair[1].starts_with('/'))
+}
+
+/// The first `http(s)://` token in `line` that `accept` agrees to.
+/// cloudflared draws a box around its URL, so `|` also ends a token.
+fn find_url(line: &str, accept: impl Fn(&str) -> bool) -> Option<String> {
+    line.split(|c: char| c.is_whitespace() || c == '|')
+        .filter(|token| token.starts_with("https://") || token.starts_with("http://"))
+        .find(|token| accept(token))
+        .map(str::to_string)
+}
+
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', ':', '?']).next().unwrap_or(rest)
+}
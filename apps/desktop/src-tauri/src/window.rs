@@ -1,40 +1,97 @@
-use tauri::Manager;
-use std::path::PathBuf;
-use std::process::Stdio;
+use crate::commands::{ServerStatus, TunnelStatus};
+use crate::error::AppError;
+use crate::AppState;
+use tauri::webview::{PageLoadEvent, PageLoadPayload};
+use tauri::{Emitter, Manager};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 
 const WINDOW_LABEL: &str = "main";
-const DEFAULT_SERVER_PORT: u16 = 8787;
+/// Title of the main window while neither the server nor the tunnel runs
+const WINDOW_TITLE: &str = "Side";
+pub const DEFAULT_SERVER_PORT: u16 = 8787;
+const MAX_NAVIGATION_ENTRIES: usize = 100;
+/// Saved main window geometry, under `app_data_dir()`
+const WINDOW_STATE_FILE: &str = "window_state.json";
 
 pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     // Get the main window
     let window = app.get_webview_window(WINDOW_LABEL)
         .ok_or("Main window not found")?;
 
-    // Setup window behavior
-    window.on_window_event(|event| match event {
-        tauri::WindowEvent::CloseRequested { .. } => {
-            // For now, allow window to close
-            // TODO: Implement tray icon with minimize-to-tray behavior
+    let csp = app.config().app.security.csp.as_ref().map(|csp| csp.to_string());
+    warn_on_weak_csp(csp.as_deref());
+
+    // The window starts hidden (see tauri.conf.json) so the restored
+    // layout is in place before anything is drawn
+    restore_window_state(&window);
+    show_and_focus(&window);
+
+    crate::tray::setup(app)?;
+
+    // Closing the window only hides it; the app keeps running in the tray
+    // until "Quit" is chosen there
+    let tracked_window = window.clone();
+    window.on_window_event(move |event| match event {
+        tauri::WindowEvent::CloseRequested { api, .. } => {
+            save_window_state(&tracked_window);
+            api.prevent_close();
+            let _ = tracked_window.hide();
+        }
+        // Quitting; don't leave the server or tunnel behind even if the
+        // frontend never called stop_all
+        tauri::WindowEvent::Destroyed => {
+            let app = tracked_window.app_handle();
+            let result = tauri::async_runtime::block_on(crate::commands::stop_all(app.state::<AppState>()));
+            if let Ok(result) = result {
+                for error in result.server_error.iter().chain(&result.tunnel_error) {
+                    tracing::error!("Cleanup on exit failed: {}", error);
+                }
+            }
         }
         _ => {}
     });
 
+    let (auto_start_server, auto_start_tunnel) = {
+        let state = app.state::<AppState>();
+        let config = state.config.blocking_lock();
+        (config.auto_start_server, config.auto_start_tunnel)
+    };
+    if !auto_start_server {
+        return Ok(());
+    }
+
     // Auto-start server when app launches
     let app_handle = app.handle().clone();
     tauri::async_runtime::spawn(async move {
         // Wait a moment for the window to initialize
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
-        // Find and start the server
-        match find_and_start_server().await {
-            Ok(_) => {
-                println!("[Desktop] Server started successfully on port {}", DEFAULT_SERVER_PORT);
+        // Started like any other start_server call, so it is tracked in
+        // AppState with its crash watcher, logs and PID file
+        let state = app_handle.state::<AppState>();
+        match crate::commands::start_server(app_handle.state(), None, None).await {
+            Ok(message) => {
+                tracing::info!("{}", message);
+                // Fallback may have moved the server off the configured port
+                let port = state.last_server_port.load(std::sync::atomic::Ordering::SeqCst);
+                if auto_start_tunnel {
+                    if let Err(e) = crate::commands::start_tunnel(app_handle.state(), port, None, None, None).await {
+                        tracing::error!("Failed to auto-start tunnel: {}", e);
+                    }
+                }
             }
             Err(e) => {
-                eprintln!("[Desktop] Failed to start server: {}", e);
+                tracing::error!("Failed to start server: {}", e);
                 // Show error to user
                 if let Some(window) = app_handle.get_webview_window(WINDOW_LABEL) {
-                    let _ = window.eval(&format!("alert('Failed to start backend server: {}\\n\\nPlease make sure Node.js is installed and accessible.');", e));
+                    let message = format!(
+                        "Failed to start backend server: {}\n\nPlease make sure Node.js is installed and accessible.",
+                        e
+                    );
+                    // JSON-encode so quotes and newlines in `e` (e.g. captured stderr) stay inside the string
+                    let literal = serde_json::to_string(&message).unwrap_or_default();
+                    let _ = window.eval(format!("alert({});", literal));
                 }
             }
         }
@@ -43,102 +100,486 @@ pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn find_and_start_server() -> Result<(), String> {
-    // Try to find Node.js and start the server
-    let node_cmd = find_node_command()?;
-
-    // Determine server directory
-    let server_dir = find_server_directory()?;
-
-    // Start the server using node
-    let mut cmd = tokio::process::Command::new(&node_cmd);
-    cmd.current_dir(&server_dir);
-    cmd.arg("dist/index.js")
-        .arg("--port")
-        .arg(DEFAULT_SERVER_PORT.to_string())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .kill_on_drop(true);
-
-    let child = cmd.spawn()
-        .map_err(|e| format!("Failed to start server: {}", e))?;
-
-    // The server process runs in background
-    tokio::spawn(async move {
-        match child.wait_with_output().await {
-            Ok(output) => {
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    eprintln!("[Desktop] Server process exited with error: {}", stderr);
-                }
-            }
-            Err(e) => {
-                eprintln!("[Desktop] Server process error: {}", e);
+/// Main window geometry saved between launches, in physical pixels.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct WindowState {
+    width: f64,
+    height: f64,
+    x: f64,
+    y: f64,
+    maximized: bool,
+}
+
+fn window_state_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path().app_data_dir()
+        .map(|dir| dir.join(WINDOW_STATE_FILE))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn load_window_state(app: &tauri::AppHandle) -> Option<WindowState> {
+    let path = window_state_path(app).ok()?;
+    let content = std::fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Apply the saved size/position, unless it no longer overlaps any monitor,
+/// in which case the tauri.conf.json defaults are kept.
+fn restore_window_state(window: &tauri::WebviewWindow) {
+    let Some(state) = load_window_state(window.app_handle()) else {
+        return;
+    };
+
+    if !is_on_screen(window, &state) {
+        tracing::info!("Saved window position is off-screen, using defaults");
+        return;
+    }
+
+    let _ = window.set_size(tauri::PhysicalSize::new(state.width, state.height));
+    let _ = window.set_position(tauri::PhysicalPosition::new(state.x, state.y));
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+fn is_on_screen(window: &tauri::WebviewWindow, state: &WindowState) -> bool {
+    let Ok(monitors) = window.available_monitors() else {
+        // Can't tell; trust the saved position
+        return true;
+    };
+
+    monitors.iter().any(|monitor| {
+        let (pos, size) = (monitor.position(), monitor.size());
+        let (left, top) = (pos.x as f64, pos.y as f64);
+        let (right, bottom) = (left + size.width as f64, top + size.height as f64);
+        state.x < right && state.x + state.width > left
+            && state.y < bottom && state.y + state.height > top
+    })
+}
+
+/// The last server and tunnel status sent to the frontend, which the title
+/// bar mirrors.
+#[derive(Default)]
+pub struct TitleStatus {
+    pub server: ServerStatus,
+    pub tunnel: TunnelStatus,
+}
+
+/// Apply `change` to the statuses in `AppState` and retitle the main window
+/// to match. Called whenever `server:state` or `tunnel:state` is emitted.
+pub fn update_title_status(app: &tauri::AppHandle, change: impl FnOnce(&mut TitleStatus)) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let mut status = state.title_status.lock().unwrap_or_else(|e| e.into_inner());
+    change(&mut status);
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        update_window_title(&window, &status.server, &status.tunnel);
+    }
+}
+
+/// Show the server and tunnel state in the title bar, and so in the
+/// taskbar, e.g. `Side — Server ● Port 8787 | Tunnel ○`.
+pub fn update_window_title(window: &tauri::WebviewWindow, server: &ServerStatus, tunnel: &TunnelStatus) {
+    if let Err(e) = window.set_title(&window_title(server, tunnel)) {
+        tracing::warn!("Failed to update window title: {}", e);
+    }
+}
+
+fn window_title(server: &ServerStatus, tunnel: &TunnelStatus) -> String {
+    if !server.running && !tunnel.running {
+        return WINDOW_TITLE.to_string();
+    }
+    let server = if server.running {
+        format!("Server \u{25cf} Port {}", server.port)
+    } else {
+        "Server \u{25cb}".to_string()
+    };
+    let tunnel = if tunnel.running { "Tunnel \u{25cf}" } else { "Tunnel \u{25cb}" };
+    format!("{} \u{2014} {} | {}", WINDOW_TITLE, server, tunnel)
+}
+
+/// Bring the (possibly hidden) main window back to the front.
+pub fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        show_and_focus(&window);
+    }
+}
+
+/// Show `window`, restore it if minimized and focus it. macOS can still
+/// leave it behind the active app, so there the dock icon bounces as well.
+/// Does nothing to a window that is already visible and focused, which
+/// would otherwise flash.
+pub fn show_and_focus(window: &tauri::WebviewWindow) {
+    let visible = window.is_visible().unwrap_or(false);
+    let focused = window.is_focused().unwrap_or(false);
+    let minimized = window.is_minimized().unwrap_or(false);
+    if visible && focused && !minimized {
+        return;
+    }
+
+    let _ = window.show();
+    let _ = window.unminimize();
+    let _ = window.set_focus();
+    #[cfg(target_os = "macos")]
+    let _ = window.request_user_attention(Some(tauri::UserAttentionType::Informational));
+}
+
+/// Save the main window's layout; used when quitting from the tray, which
+/// skips `CloseRequested`.
+pub fn save_main_window_state(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        // Hidden windows still report their last geometry
+        save_window_state(&window);
+    }
+}
+
+fn save_window_state(window: &tauri::WebviewWindow) {
+    let maximized = window.is_maximized().unwrap_or(false);
+
+    // A maximized window's geometry is the monitor's; keep the last normal
+    // layout so un-maximizing next launch goes back to it
+    let state = match (maximized, load_window_state(window.app_handle())) {
+        (true, Some(previous)) => WindowState { maximized: true, ..previous },
+        _ => {
+            let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+                return;
+            };
+            WindowState {
+                width: size.width as f64,
+                height: size.height as f64,
+                x: position.x as f64,
+                y: position.y as f64,
+                maximized,
             }
         }
+    };
+
+    let result = window_state_path(window.app_handle()).and_then(|path| {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&state).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
     });
+    if let Err(e) = result {
+        tracing::error!("Failed to save window state: {}", e);
+    }
+}
 
-    // Wait a bit for server to start
-    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-
-    // Verify server is running
-    let health_url = format!("http://localhost:{}/health", DEFAULT_SERVER_PORT);
-    match tokio::process::Command::new("curl")
-        .arg("-s")
-        .arg("-o")
-        .arg("nul")
-        .arg(&health_url)
-        .output()
-        .await
-    {
-        Ok(_) => Ok(()),
-        Err(_) => Err("Server did not respond to health check".to_string()),
+/// Browser-style back/forward history for the main webview.
+#[derive(Default)]
+pub struct NavigationHistory {
+    entries: VecDeque<String>,
+    cursor: usize,
+}
+
+impl NavigationHistory {
+    /// Record a new page, dropping anything ahead of the cursor. Pushing the
+    /// current URL again is a no-op, which is what happens when the page
+    /// load for a back/forward navigation comes in.
+    pub fn push(&mut self, url: String) {
+        if self.current() == Some(url.as_str()) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.cursor + 1);
+        }
+        self.entries.push_back(url);
+        if self.entries.len() > MAX_NAVIGATION_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.cursor = self.entries.len() - 1;
+    }
+
+    pub fn back(&mut self) -> Option<&str> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.cursor -= 1;
+        self.current()
+    }
+
+    pub fn forward(&mut self) -> Option<&str> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.cursor += 1;
+        self.current()
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.cursor + 1 < self.entries.len()
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.entries.get(self.cursor).map(String::as_str)
     }
 }
 
-fn find_node_command() -> Result<String, String> {
-    // Try common Node.js command names
-    let candidates = ["node", "node.exe"];
+#[derive(Clone, serde::Serialize)]
+pub struct NavigationEvent {
+    pub url: String,
+    pub can_go_back: bool,
+    pub can_go_forward: bool,
+}
 
-    for cmd in candidates {
-        if which::which(cmd).is_ok() {
-            return Ok(cmd.to_string());
+impl From<&NavigationHistory> for NavigationEvent {
+    fn from(history: &NavigationHistory) -> Self {
+        NavigationEvent {
+            url: history.current().unwrap_or_default().to_string(),
+            can_go_back: history.can_go_back(),
+            can_go_forward: history.can_go_forward(),
         }
     }
+}
+
+/// Registered with `Builder::on_page_load` so every finished page load is
+/// recorded in the navigation history.
+pub fn on_page_load(webview: &tauri::Webview, payload: &PageLoadPayload<'_>) {
+    if payload.event() != PageLoadEvent::Finished || webview.label() != WINDOW_LABEL {
+        return;
+    }
 
-    Err("Node.js not found in PATH. Please install Node.js to run the backend server.".to_string())
+    let app = webview.app_handle();
+    // Config windows can finish loading before setup has managed the state
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let event = {
+        let Ok(mut history) = state.navigation.lock() else {
+            return;
+        };
+        history.push(payload.url().to_string());
+        NavigationEvent::from(&*history)
+    };
+    let _ = app.emit("app://navigation", event);
 }
 
-fn find_server_directory() -> Result<PathBuf, String> {
-    // In production, the server should be bundled relative to the exe
-    let exe_path = std::env::current_exe()
-        .map_err(|e| format!("Failed to get exe path: {}", e))?;
+pub fn navigate(app: &tauri::AppHandle, url: &str) -> Result<(), AppError> {
+    let window = app.get_webview_window(WINDOW_LABEL)
+        .ok_or("Main window not found")?;
+    let url = tauri::Url::parse(url)
+        .map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+    window.navigate(url)
+        .map_err(|e| format!("Failed to navigate: {}", e))?;
+    Ok(())
+}
 
-    let mut server_path = exe_path;
-    server_path.pop(); // Remove exe name
+/// Log a warning if the webview runs without a CSP, an empty one or one
+/// allowing inline scripts.
+fn warn_on_weak_csp(csp: Option<&str>) {
+    let Some(csp) = csp else {
+        tracing::warn!("No content security policy is set");
+        return;
+    };
+    if csp.trim().is_empty() {
+        tracing::warn!("Content security policy is empty");
+    } else if csp.contains("'unsafe-inline'") {
+        tracing::warn!("Content security policy allows 'unsafe-inline'");
+    }
+}
 
-    // In development, go up to find the project root
-    // In production, server should be in a known relative path
-    #[cfg(debug_assertions)]
-    {
-        // Development: search up for project root
-        for _ in 0..10 {
-            let server_dir = server_path.join("apps").join("server");
-            if server_dir.exists() && server_dir.join("package.json").exists() {
-                return Ok(server_dir);
-            }
-            if !server_path.pop() {
-                break;
-            }
+/// Open `dir` in the system file manager.
+pub fn open_in_file_manager(app: &tauri::AppHandle, dir: &Path) -> Result<(), AppError> {
+    use tauri_plugin_shell::ShellExt;
+
+    // Opening from Rust isn't subject to the plugin's URL scope
+    #[allow(deprecated)]
+    app.shell()
+        .open(dir.to_string_lossy(), None)
+        .map_err(|e| file_manager_error(dir, e))
+}
+
+/// Select `path` in the file manager: `explorer /select,` on Windows,
+/// `open -R` on macOS and `nautilus --select` on Linux when Nautilus is
+/// installed. Other Linux file managers can't select a file, so they get
+/// its folder through `xdg-open`.
+pub async fn reveal_in_file_manager(path: &Path) -> Result<(), AppError> {
+    if !path.exists() {
+        return Err(format!("{} does not exist", path.display()).into());
+    }
+
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = tokio::process::Command::new("explorer");
+        // Explorer wants the path glued to the flag
+        command.arg(format!("/select,{}", path.display()));
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = tokio::process::Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = if which::which("nautilus").is_ok() {
+        let mut command = tokio::process::Command::new("nautilus");
+        command.arg("--select").arg(path);
+        command
+    } else {
+        let mut command = tokio::process::Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    };
+
+    // Explorer exits non-zero even when it worked, so only a failure to
+    // launch counts
+    command.spawn().map_err(|e| file_manager_error(path, e))?;
+    Ok(())
+}
+
+fn file_manager_error(path: &Path, error: impl std::fmt::Display) -> AppError {
+    let hint = if cfg!(all(unix, not(target_os = "macos"))) {
+        " Opening files needs xdg-open (usually from the xdg-utils package)."
+    } else {
+        ""
+    };
+    AppError::Other(format!("Failed to open {} in the file manager: {}.{}", path.display(), error, hint))
+}
+
+/// Capture the main window's client area and save it as a PNG under
+/// `app_data_dir()/screenshots`. Returns the path of the saved file.
+/// Windows only; see `capture_main_window_png`.
+pub fn capture_screenshot(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    // Fail before creating the directory on platforms that can't capture
+    if !cfg!(windows) {
+        return Err(AppError::Other(SCREENSHOT_UNSUPPORTED.to_string()));
+    }
+    let screenshots_dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?
+        .join("screenshots");
+    std::fs::create_dir_all(&screenshots_dir)
+        .map_err(|e| format!("Failed to create screenshots directory: {}", e))?;
+
+    let png = capture_main_window_png(app)?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = screenshots_dir.join(format!("{}.png", timestamp));
+    std::fs::write(&path, png)
+        .map_err(|e| format!("Failed to write screenshot: {}", e))?;
+
+    Ok(path)
+}
+
+/// Returned by the screenshot functions outside Windows
+const SCREENSHOT_UNSUPPORTED: &str = "Screenshot capture is only supported on Windows";
+
+/// The main window's contents as PNG bytes. Fails while the window is
+/// hidden or minimized, as there is nothing to capture then.
+///
+/// Only implemented on Windows (through `PrintWindow`). On macOS and Linux
+/// this always returns an error, and the diagnostics bundle lists the
+/// screenshot as missing.
+pub fn capture_main_window_png(app: &tauri::AppHandle) -> Result<Vec<u8>, AppError> {
+    let window = app.get_webview_window(WINDOW_LABEL)
+        .ok_or("Main window not found")?;
+    if !window.is_visible().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+        return Err("Main window is hidden or minimized".into());
+    }
+    Ok(capture_window_png(&window)?)
+}
+
+#[cfg(windows)]
+fn capture_window_png(window: &tauri::WebviewWindow) -> Result<Vec<u8>, String> {
+    use windows_sys::Win32::Foundation::RECT;
+    use windows_sys::Win32::Graphics::Gdi::{
+        CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+        ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows_sys::Win32::Storage::Xps::{PrintWindow, PW_CLIENTONLY};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetClientRect, PW_RENDERFULLCONTENT};
+
+    let hwnd = window.hwnd()
+        .map_err(|e| format!("Failed to get window handle: {}", e))?
+        .0;
+
+    let (width, height) = unsafe {
+        let mut rect: RECT = std::mem::zeroed();
+        if GetClientRect(hwnd, &mut rect) == 0 {
+            return Err("Failed to get window client area".to_string());
         }
-        Err("Could not find server directory. Please run from the project root.".to_string())
+        (rect.right - rect.left, rect.bottom - rect.top)
+    };
+    if width <= 0 || height <= 0 {
+        return Err("Window has no visible client area (is it minimized?)".to_string());
     }
 
-    #[cfg(not(debug_assertions))]
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    unsafe {
+        let window_dc = GetDC(hwnd);
+        let mem_dc = CreateCompatibleDC(window_dc);
+        let bitmap = CreateCompatibleBitmap(window_dc, width, height);
+        let previous = SelectObject(mem_dc, bitmap);
+
+        // The webview is composited by DWM, which only PrintWindow with
+        // PW_RENDERFULLCONTENT draws; unlike copying from the screen it
+        // can't pick up windows lying on top
+        let copied = PrintWindow(hwnd, mem_dc, PW_CLIENTONLY | PW_RENDERFULLCONTENT);
+
+        let mut info: BITMAPINFO = std::mem::zeroed();
+        info.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
+        info.bmiHeader.biWidth = width;
+        // Negative height requests a top-down DIB so rows come out in PNG order
+        info.bmiHeader.biHeight = -height;
+        info.bmiHeader.biPlanes = 1;
+        info.bmiHeader.biBitCount = 32;
+        info.bmiHeader.biCompression = BI_RGB;
+
+        let lines = GetDIBits(
+            mem_dc,
+            bitmap,
+            0,
+            height as u32,
+            pixels.as_mut_ptr().cast(),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+
+        SelectObject(mem_dc, previous);
+        DeleteObject(bitmap);
+        DeleteDC(mem_dc);
+        ReleaseDC(hwnd, window_dc);
+
+        if copied == 0 || lines == 0 {
+            return Err("Failed to copy window contents".to_string());
+        }
+    }
+
+    // GDI hands back BGRA with an undefined alpha channel
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+        pixel[3] = 0xff;
+    }
+
+    let mut png = Vec::new();
     {
-        // Production: server is bundled in resources/server/
-        // Structure: side-desktop.exe -> resources/server/dist/index.js
-        let resources_dir = server_path.join("resources");
-        Ok(resources_dir.join("server"))
+        let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()
+            .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
+        writer.write_image_data(&pixels)
+            .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
     }
+    Ok(png)
+}
+
+#[cfg(not(windows))]
+fn capture_window_png(_window: &tauri::WebviewWindow) -> Result<Vec<u8>, String> {
+    Err(SCREENSHOT_UNSUPPORTED.to_string())
 }
@@ -0,0 +1,100 @@
+//! Starts and stops a real Node.js process through `server::start` and
+//! `server::stop`, with a small stand-in script in place of the bundled
+//! server. Skipped when Node.js isn't installed.
+
+use side_desktop_lib::server::{self, LogBuffer, ServerConfig};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Prints the same ready banner as the real server, then keeps running
+/// until it is stopped.
+const MOCK_SERVER: &str = r#"
+const http = require("http");
+const port = Number(process.env.PORT);
+http
+  .createServer((req, res) => res.end("ok"))
+  .listen(port, "127.0.0.1", () => console.log(`server listening on ${port}`));
+"#;
+
+const READY_LINE: &str = "server listening on";
+const LOG_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Kills the server process if the test fails before stopping it.
+struct KillOnDrop {
+    pid: Option<u32>,
+}
+
+impl KillOnDrop {
+    fn disarm(&mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let Some(pid) = self.pid else {
+            return;
+        };
+        #[cfg(unix)]
+        let _ = std::process::Command::new("kill").args(["-9", &pid.to_string()]).status();
+        #[cfg(windows)]
+        let _ = std::process::Command::new("taskkill")
+            .args(["/F", "/T", "/PID", &pid.to_string()])
+            .status();
+    }
+}
+
+/// A fresh directory holding the mock server script.
+fn write_mock_server() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("side-mock-server-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let script = dir.join("index.js");
+    std::fs::write(&script, MOCK_SERVER).expect("write mock server");
+    script
+}
+
+#[tokio::test]
+async fn start_logs_output_and_stop_ends_the_process() {
+    if server::find_node_executable().is_err() {
+        eprintln!("Node.js not found, skipping");
+        return;
+    }
+    let script = write_mock_server();
+
+    let config = ServerConfig::builder()
+        // Let the OS pick a free port
+        .port(0)
+        .script(&script)
+        .working_dir(script.parent().unwrap())
+        .build();
+    let logs = LogBuffer::new(100);
+    let handle = server::start(config, logs.clone()).await.expect("server should start");
+    let mut guard = KillOnDrop { pid: handle.pid() };
+    let port = handle.port;
+
+    let deadline = Instant::now() + LOG_TIMEOUT;
+    while !logs.recent(100).iter().any(|line| line.message.contains(READY_LINE)) {
+        assert!(
+            Instant::now() < deadline,
+            "no ready line within {:?}; got {:?}",
+            LOG_TIMEOUT,
+            logs.recent(100).iter().map(|l| &l.message).collect::<Vec<_>>()
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // Generous margin over the SIGTERM grace period for slow CI machines
+    let grace = server::DEFAULT_SHUTDOWN_TIMEOUT + Duration::from_secs(2);
+    tokio::time::timeout(grace, server::stop(handle))
+        .await
+        .expect("server should exit within the grace period")
+        .expect("stop should succeed");
+    guard.disarm();
+
+    assert!(
+        std::net::TcpListener::bind(("127.0.0.1", port)).is_ok(),
+        "port {} should be free once the server has stopped",
+        port
+    );
+    let _ = std::fs::remove_dir_all(script.parent().unwrap());
+}
@@ -2,7 +2,7 @@ import crypto from "node:crypto";
 import fsSync from "node:fs";
 import type { Server } from "node:http";
 import { DatabaseSync } from "node:sqlite";
-import { serve } from "@hono/node-server";
+import { createAdaptorServer, serve } from "@hono/node-server";
 import { serveStatic } from "@hono/node-server/serve-static";
 import type { MiddlewareHandler } from "hono";
 import { Hono } from "hono";
@@ -227,8 +227,16 @@ export function createServer(portOverride?: number) {
     });
   }
 
-  // Start server
-  const server = serve({ fetch: app.fetch, port: serverPort, hostname: HOST }) as Server;
+  // Start server. The desktop app may hand over a socket it has already
+  // bound (NODE_SOCKET_FD), so that no other process can take the port first.
+  const socketFd = Number(process.env.NODE_SOCKET_FD);
+  let server: Server;
+  if (process.env.NODE_SOCKET_FD && Number.isInteger(socketFd)) {
+    server = createAdaptorServer({ fetch: app.fetch }) as Server;
+    server.listen({ fd: socketFd });
+  } else {
+    server = serve({ fetch: app.fetch, port: serverPort, hostname: HOST }) as Server;
+  }
 
   // Setup WebSocket
   setupWebSocketServer(server, terminals);